@@ -0,0 +1,60 @@
+//! Throughput of the generic readers against their boxed versions, and of the lexer reading
+//! its input in place.
+//!
+//! Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use libporte::automaton::{Automaton, BoxedAutomaton};
+use libporte::chars::{BoxedChars, Chars};
+use libporte::lexer_bytes::LexerBytes;
+use libporte::lexer_iter::{BoxedLexerIter, LexerIter};
+
+const ROUNDS: u32 = 5;
+
+/// A document of about 4 MiB, mixing every kind of token.
+fn document() -> String {
+    let item = r#"{"name": "Jérôme \"le grand\"", "age": 42, "ratio": -1.25e-3, "tags": ["a", "b\n", "𝄞"], "ok": true, "no": false, "none": null}"#;
+    let mut s = String::from("[\n");
+    while s.len() < 4 * 1024 * 1024 {
+        s.push_str("  ");
+        s.push_str(item);
+        s.push_str(",\n");
+    }
+    s.push_str("  0\n]");
+    s
+}
+
+/// Runs `f` a few times on `input`, and prints the best throughput.
+fn bench(name: &str, input: &str, f: impl Fn(&str) -> usize) {
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        black_box(f(black_box(input)));
+        best = best.min(start.elapsed());
+    }
+    let mib = input.len() as f64 / (1024.0 * 1024.0);
+    println!("{:<16} {:>8.1} MiB/s", name, mib / best.as_secs_f64());
+}
+
+fn main() {
+    let input = document();
+    bench("chars", &input, |s| Chars::new(s.bytes()).count());
+    bench("chars boxed", &input, |s| BoxedChars::boxed(s.bytes()).count());
+    bench("lexer", &input, |s| LexerIter::new(Chars::new(s.bytes())).count());
+    bench("lexer boxed", &input, |s| {
+        let lexer: BoxedLexerIter = LexerIter::new(BoxedChars::boxed(s.bytes()));
+        lexer.count()
+    });
+    bench("lexer bytes", &input, |s| LexerBytes::new(s.as_bytes()).count());
+    bench("lexer str", &input, |s| LexerBytes::from(s).count());
+    bench("automaton", &input, |s| {
+        Automaton::new(LexerIter::new(Chars::new(s.bytes()))).count()
+    });
+    bench("automaton boxed", &input, |s| {
+        let lexer: BoxedLexerIter = LexerIter::new(BoxedChars::boxed(s.bytes()));
+        let automaton: BoxedAutomaton = Automaton::new(Box::new(lexer));
+        automaton.count()
+    });
+}
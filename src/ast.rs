@@ -1,11 +1,11 @@
-use crate::{automaton::{Action, Automaton}, err::{TokenizeError, internal_error}, lexer::Lexer, value::Value};
+use crate::{automaton::{Action, Automaton}, err::{TokenizeError, internal_error}, lexer::Lexer, token::JsonString, value::Value};
 
 /// Parse the input (and check that it's valid as whole)
-pub fn parse_and_valid<'a>(input: Box<dyn Lexer + 'a>) -> Result<Value, TokenizeError> {
+pub fn parse_and_valid<'a>(input: impl Lexer<'a>) -> Result<Value<'a>, TokenizeError> {
     let automaton = Automaton::new(input);
-    let mut stack: Vec<Stack> = Vec::new();
-    let mut keys: Vec<String> = Vec::new();
-    let mut value: Option<Value> = None;
+    let mut stack: Vec<Stack<'a>> = Vec::new();
+    let mut keys: Vec<JsonString<'a>> = Vec::new();
+    let mut value: Option<Value<'a>> = None;
     for action in automaton {
         match action? {
             Action::Nothing => continue,
@@ -36,9 +36,9 @@ pub fn parse_and_valid<'a>(input: Box<dyn Lexer + 'a>) -> Result<Value, Tokenize
     value.ok_or_else(|| internal_error!())
 }
 
-enum Stack {
-    Array(Vec<Value>),
-    Object(Vec<(String, Value)>),
+enum Stack<'a> {
+    Array(Vec<Value<'a>>),
+    Object(Vec<(JsonString<'a>, Value<'a>)>),
 }
 
 #[cfg(test)]
@@ -55,6 +55,42 @@ mod tests {
         p.unwrap();
     }
 
+    #[test]
+    fn in_another_thread() {
+        let s = LexerIter::new(Chars::from(r#"{"a": [1, 2]}"#));
+        let p = std::thread::spawn(move || parse_and_valid(s)).join();
+        assert!(matches!(p, Ok(Ok(Value::Object(_)))));
+    }
+
+    #[test]
+    fn boxed() {
+        use crate::chars::BoxedChars;
+        use crate::lexer_iter::BoxedLexerIter;
+        let s: BoxedLexerIter = LexerIter::new(BoxedChars::boxed("[true]".bytes()));
+        let p = parse_and_valid(Box::new(s) as Box<dyn Lexer>);
+        assert!(matches!(p, Ok(Value::Array(_))));
+    }
+
+    #[test]
+    fn io_error() {
+        struct Failing(&'static [u8]);
+        impl std::io::Read for Failing {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.0.is_empty() {
+                    Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "gone"))
+                } else {
+                    let n = self.0.read(buf)?;
+                    Ok(n)
+                }
+            }
+        }
+        let s = LexerIter::new(Chars::from_reader(Failing(b"[1, 2")));
+        match parse_and_valid(Box::new(s)) {
+            Err(TokenizeError::Io(e)) => assert_eq!(std::io::ErrorKind::BrokenPipe, e.kind()),
+            r => unreachable!("{:?}", r),
+        }
+    }
+
     #[test]
     fn complete() {
         let s = r#"
@@ -84,4 +120,18 @@ mod tests {
         let p = parse_and_valid(Box::new(s));
         p.unwrap();
     }
+
+    #[test]
+    fn lone_surrogates() {
+        use crate::lexer::Surrogates;
+        let s = LexerIter::new(Chars::from(r#"{"\uDC00": ["\uD834", "a"]}"#)).with_surrogates(Surrogates::Wtf8);
+        let expected = Value::Object(vec![(
+            JsonString::Wtf8(vec![0xED, 0xB0, 0x80]),
+            Value::Array(vec![
+                Value::String(JsonString::Wtf8(vec![0xED, 0xA0, 0xB4])),
+                Value::String("a".into()),
+            ]),
+        )]);
+        assert_eq!(Ok(expected), parse_and_valid(s));
+    }
 }
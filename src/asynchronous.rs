@@ -0,0 +1,269 @@
+//! Asynchronous reading of the input, the actions being yielded as a stream.
+//!
+//! Nothing here depends on an async runtime: any reader implementing [`AsyncRead`] can be
+//! adapted to it.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{
+    automaton::Action,
+    chars::Position,
+    err::TokenizeError,
+    push::PushParser,
+};
+
+/// Size of the chunks read from the reader.
+const READ_BUFFER_SIZE: usize = 8 * 1024;
+
+/// A source of bytes which can be read without blocking, like the `AsyncRead` of the async
+/// runtimes.
+pub trait AsyncRead {
+    /// Attempts to read some bytes into `buf`, returning how many were read (`0` at the end of
+    /// the input).
+    ///
+    /// When no byte is available yet, it returns `Poll::Pending` and arranges for the waker of
+    /// `cx` to be woken once some are.
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>>;
+}
+
+impl<R: AsyncRead + Unpin + ?Sized> AsyncRead for &mut R {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self).poll_read(cx, buf)
+    }
+}
+
+impl<R: AsyncRead + Unpin + ?Sized> AsyncRead for Box<R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self).poll_read(cx, buf)
+    }
+}
+
+/// A stream of the [`Action`]s of an automaton reading an [`AsyncRead`].
+///
+/// The input is read by chunks, which are given to a [`PushParser`]: it must be UTF-8. Like an
+/// [`crate::automaton::Automaton`], the stream ends after [`Action::TheEnd`] or after the first
+/// error.
+pub struct AsyncActions<R> {
+    reader: R,
+    parser: PushParser,
+    buffer: Box<[u8]>,
+    actions: VecDeque<Action<'static>>,
+    /// The error to yield once the actions before it are
+    error: Option<TokenizeError>,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncActions<R> {
+    /// Create a new one
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            parser: PushParser::new(),
+            buffer: vec![0; READ_BUFFER_SIZE].into_boxed_slice(),
+            actions: VecDeque::new(),
+            error: None,
+            done: false,
+        }
+    }
+
+    /// Returns the position of the first byte not lexed yet (or where the lexical error occured).
+    #[must_use]
+    pub fn current_position(&self) -> Position {
+        self.parser.current_position()
+    }
+
+    /// Attempts to get the next action, `Poll::Ready(None)` once the stream is over.
+    pub fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Action<'static>, TokenizeError>>> {
+        let this = &mut *self;
+        loop {
+            if let Some(a) = this.actions.pop_front() {
+                return Poll::Ready(Some(Ok(a)));
+            }
+            if let Some(e) = this.error.take() {
+                return Poll::Ready(Some(Err(e)));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            let mut actions = Vec::new();
+            let r = match Pin::new(&mut this.reader).poll_read(cx, &mut this.buffer) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(e)) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Poll::Ready(Err(e)) => Err(e.into()),
+                Poll::Ready(Ok(0)) => {
+                    this.done = true;
+                    this.parser.finish(&mut actions)
+                }
+                Poll::Ready(Ok(n)) => this.parser.feed(&this.buffer[..n], &mut actions).map(|_| ()),
+            };
+            this.actions.extend(actions);
+            if let Err(e) = r {
+                this.error = Some(e);
+                this.done = true;
+            }
+        }
+    }
+
+    /// Returns the next action, `None` once the stream is over.
+    pub fn next_action(&mut self) -> NextAction<'_, R> {
+        NextAction { actions: self }
+    }
+}
+
+/// The future returned by [`AsyncActions::next_action`].
+pub struct NextAction<'a, R> {
+    actions: &'a mut AsyncActions<R>,
+}
+
+impl<R: AsyncRead + Unpin> Future for NextAction<'_, R> {
+    type Output = Option<Result<Action<'static>, TokenizeError>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.actions).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{automaton::Automaton, chars::Chars, lexer_iter::LexerIter};
+    use std::task::Waker;
+
+    /// A reader giving its input by small chunks, and returning `Pending` at random.
+    struct Random<'a> {
+        input: &'a [u8],
+        /// State of a xorshift generator
+        seed: u32,
+        pendings: usize,
+    }
+
+    impl Random<'_> {
+        fn random(&mut self) -> u32 {
+            self.seed ^= self.seed << 13;
+            self.seed ^= self.seed >> 17;
+            self.seed ^= self.seed << 5;
+            self.seed
+        }
+    }
+
+    impl AsyncRead for Random<'_> {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            let r = self.random();
+            if r.is_multiple_of(3) {
+                self.pendings += 1;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = (r as usize / 3 % 5 + 1).min(buf.len()).min(self.input.len());
+            buf[..n].copy_from_slice(&self.input[..n]);
+            self.input = &self.input[n..];
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    /// Runs `future` to completion, polling it again each time it is pending.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(r) = future.as_mut().poll(&mut cx) {
+                return r;
+            }
+        }
+    }
+
+    /// Collects the stream, stopping at the first error.
+    async fn collect<R: AsyncRead + Unpin>(mut actions: AsyncActions<R>) -> (Vec<Action<'static>>, Result<(), TokenizeError>) {
+        let mut r = Vec::new();
+        while let Some(a) = actions.next_action().await {
+            match a {
+                Ok(a) => r.push(a),
+                Err(e) => return (r, Err(e)),
+            }
+        }
+        (r, Ok(()))
+    }
+
+    #[test]
+    fn same_actions_as_automaton() {
+        let documents = [
+            r#"{"a": [1, -2.5e+3, true, false, null], "bé\"\\": {"c": "d𝄞𝄞"}}"#,
+            "  123456789  ",
+            "[]",
+            "[1, 2",
+            r#"["abc\u00"]"#,
+            "[1] 2",
+        ];
+        for document in documents {
+            let mut expected = Vec::new();
+            let mut end = Ok(());
+            for a in Automaton::new(LexerIter::new(Chars::from(document))) {
+                match a {
+                    Ok(a) => expected.push(a),
+                    Err(e) => {
+                        end = Err(e);
+                        break;
+                    }
+                }
+            }
+            let mut pendings = 0;
+            for seed in 1..50 {
+                let mut reader = Random { input: document.as_bytes(), seed, pendings: 0 };
+                let (actions, r) = block_on(collect(AsyncActions::new(&mut reader)));
+                assert_eq!(expected, actions, "{} with {}", document, seed);
+                assert_eq!(end, r, "{} with {}", document, seed);
+                pendings += reader.pendings;
+            }
+            assert!(pendings > 0);
+        }
+    }
+
+    #[test]
+    fn long_string() {
+        let document = format!("{{\"a\": [\"{}\", 1]}}", "bé\\\"".repeat(1 << 17));
+        let expected: Vec<Action> = Automaton::new(LexerIter::new(Chars::from(document.as_str())))
+            .map(|a| a.unwrap())
+            .collect();
+        let mut reader = Random { input: document.as_bytes(), seed: 7, pendings: 0 };
+        // The string spans hundreds of thousands of reads
+        let (actions, r) = block_on(collect(AsyncActions::new(&mut reader)));
+        assert_eq!(Ok(()), r);
+        assert!(expected == actions);
+        assert!(reader.pendings > 0);
+    }
+
+    #[test]
+    fn io_error() {
+        struct Failing;
+        impl AsyncRead for Failing {
+            fn poll_read(self: Pin<&mut Self>, _: &mut Context<'_>, _: &mut [u8]) -> Poll<io::Result<usize>> {
+                Poll::Ready(Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset")))
+            }
+        }
+        let (actions, r) = block_on(collect(AsyncActions::new(Failing)));
+        assert!(actions.is_empty());
+        assert!(matches!(r, Err(TokenizeError::Io(e)) if e.kind() == io::ErrorKind::ConnectionReset));
+    }
+
+    #[test]
+    fn pending_does_not_block() {
+        struct Never;
+        impl AsyncRead for Never {
+            fn poll_read(self: Pin<&mut Self>, _: &mut Context<'_>, _: &mut [u8]) -> Poll<io::Result<usize>> {
+                Poll::Pending
+            }
+        }
+        let mut actions = AsyncActions::new(Never);
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(Poll::Pending, Pin::new(&mut actions).poll_next(&mut cx));
+    }
+}
@@ -1,25 +1,111 @@
-use crate::{err::{internal_error, TokenizeError}, lexer::Lexer, token::Token, value::Value};
+use std::{borrow::Cow, marker::PhantomData};
+
+use crate::{chars::Position, err::{internal_error, TokenizeError}, lexer::{Dialect, Lexer}, token::{JsonString, NonFinite, Span, Token}, value::Value};
 
 /// A stack-based automaton to read a stream of Tokens.
-pub struct Automaton<'a> {
-    lexer: Box<dyn Lexer + 'a>,
+///
+/// The strings of the tokens are passed as they are to the actions, so they still borrow from
+/// the input for `'a` if the lexer does.
+pub struct Automaton<'a, L> {
+    lexer: L,
+    machine: Machine,
+    /// Span of the last token read
+    span: Span,
+    /// The comments read since the last call to [`Automaton::take_comments`]
+    comments: Vec<Cow<'a, str>>,
+    /// The lifetime of the strings of the tokens
+    strings: PhantomData<&'a str>,
+}
+
+/// [`Automaton`] over a boxed lexer, when the type of the lexer can't be named.
+pub type BoxedAutomaton<'a> = Automaton<'a, Box<dyn Lexer<'a> + 'a>>;
+
+impl<'a, L: Lexer<'a>> Automaton<'a, L> {
+    /// Create a new JSON Automaton, reading the dialect of the lexer
+    pub fn new(input: L) -> Self {
+        Self {
+            machine: Machine::new().with_dialect(input.dialect()),
+            lexer: input,
+            span: Span::default(),
+            comments: Vec::new(),
+            strings: PhantomData,
+        }
+    }
+
+    /// Returns the position reached by the lexer, which is where the last error occured if any.
+    #[must_use]
+    pub fn current_position(&self) -> Position {
+        self.lexer.current_position()
+    }
+
+    /// Returns the span of the token which led to the last action (or error).
+    ///
+    /// At the end of the input, it is the empty span where the input ended.
+    #[must_use]
+    pub fn token_span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns the comments read since the last call, which precede the token of the last action.
+    ///
+    /// Taking them after each [`Action::NewKey`], [`Action::Push`], [`Action::NewArray`] or
+    /// [`Action::NewObject`] attaches them to the following value. The lexer must keep the
+    /// comments (see [`crate::lexer::SourceLexer::keep_comments`]).
+    pub fn take_comments(&mut self) -> Vec<Cow<'a, str>> {
+        std::mem::take(&mut self.comments)
+    }
+}
+
+impl<'a, L: Lexer<'a>> Iterator for Automaton<'a, L> {
+    type Item = Result<Action<'a>, TokenizeError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.lexer.current_position();
+        let next = self.lexer.next();
+        self.span = Span {
+            start,
+            end: self.lexer.current_position(),
+        };
+        match next {
+            Some(Ok(Token::Comment(c))) => {
+                self.comments.push(c);
+                self.machine.step(Token::WhiteSpace)
+            }
+            Some(Ok(t)) => self.machine.step(t),
+            Some(Err(e)) => self.machine.fail(e),
+            None => self.machine.end(),
+        }
+    }
+}
+
+/// The states and the stack of an automaton, to which the tokens are given one by one.
+pub(crate) struct Machine {
     state: State,
     stack: Vec<Stack>,
     keys: usize,
+    dialect: Dialect,
 }
 
-impl<'a> Automaton<'a> {
-    /// Create a new JSON Automaton
-    pub fn new(input: Box<dyn Lexer + 'a>) -> Self {
+impl Machine {
+    pub(crate) fn new() -> Self {
         Self {
-            lexer: input,
             state: State::Begin,
             stack: Vec::new(),
             keys: 0,
+            dialect: Dialect::Json,
         }
     }
 
-    fn array_end(&mut self) -> Option<<Self as Iterator>::Item> {
+    pub(crate) fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Whether a whole JSON value was read, so that the input may end.
+    pub(crate) fn is_complete(&self) -> bool {
+        matches!(self.state, State::End)
+    }
+
+    fn array_end<'a>(&mut self) -> Option<Result<Action<'a>, TokenizeError>> {
         if let Some(Stack::Array) = self.stack.pop() {
             match self.stack.last() {
                 Some(Stack::Array) => {
@@ -40,7 +126,7 @@ impl<'a> Automaton<'a> {
         }
     }
 
-    fn object_end(&mut self) -> Option<<Self as Iterator>::Item> {
+    fn object_end<'a>(&mut self) -> Option<Result<Action<'a>, TokenizeError>> {
         if let Some(Stack::Object) = self.stack.pop() {
             match self.stack.last() {
                 Some(Stack::Array) => {
@@ -70,291 +156,355 @@ macro_rules! debug_state {
     };
 }
 
-impl Iterator for Automaton<'_> {
-    type Item = Result<Action, TokenizeError>;
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(t) = self.lexer.next() {
-            debug_state!(t, self.state);
-            match self.state {
-                State::Begin => match t {
-                    Token::ArrayBegin => {
-                        self.state = State::InArrayEmpty;
-                        self.stack.push(Stack::Array);
-                        Some(Ok(Action::NewArray))
-                    }
-                    Token::ObjectBegin => {
-                        self.state = State::InObjectEmpty;
-                        self.stack.push(Stack::Object);
-                        Some(Ok(Action::NewObject))
-                    }
-                    Token::False => {
-                        self.state = State::End;
-                        Some(Ok(Action::Push(Value::False)))
-                    }
-                    Token::True => {
-                        self.state = State::End;
-                        Some(Ok(Action::Push(Value::True)))
-                    }
-                    Token::Null => {
-                        self.state = State::End;
-                        Some(Ok(Action::Push(Value::Null)))
-                    }
-                    Token::String(s) => {
-                        self.state = State::End;
-                        Some(Ok(Action::Push(Value::String(s))))
-                    }
-                    Token::Number(n) => {
-                        self.state = State::End;
-                        Some(Ok(Action::Push(Value::Number(n))))
-                    }
-                    Token::WhiteSpace => Some(Ok(Action::Nothing)),
-                    Token::ArrayEnd
-                    | Token::ObjectEnd
-                    | Token::NameSeparator
-                    | Token::ValueSeparator => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                },
-                State::InArrayEmpty => match t {
-                    Token::ArrayBegin => {
-                        self.state = State::InArrayEmpty;
-                        self.stack.push(Stack::Array);
-                        Some(Ok(Action::NewArray))
-                    }
-                    Token::ObjectBegin => {
-                        self.state = State::InObjectEmpty;
-                        self.stack.push(Stack::Object);
-                        Some(Ok(Action::NewObject))
-                    }
-                    Token::False => {
-                        self.state = State::LastWasValueIn(Array);
-                        Some(Ok(Action::Push(Value::False)))
-                    }
-                    Token::True => {
-                        self.state = State::LastWasValueIn(Array);
-                        Some(Ok(Action::Push(Value::True)))
-                    }
-                    Token::Null => {
-                        self.state = State::LastWasValueIn(Array);
-                        Some(Ok(Action::Push(Value::Null)))
-                    }
-                    Token::String(s) => {
-                        self.state = State::LastWasValueIn(Array);
-                        Some(Ok(Action::Push(Value::String(s))))
-                    }
-                    Token::Number(n) => {
-                        self.state = State::LastWasValueIn(Array);
-                        Some(Ok(Action::Push(Value::Number(n))))
-                    }
-                    Token::WhiteSpace => Some(Ok(Action::Nothing)),
-                    Token::ArrayEnd => self.array_end(),
-                    Token::ValueSeparator => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                    Token::NameSeparator | Token::ObjectEnd => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                },
-                State::LastWasValueIn(Array) => match t {
-                    Token::ValueSeparator => {
-                        self.state = State::InArrayLastWasDelim;
-                        Some(Ok(Action::Nothing))
-                    }
-                    Token::ArrayEnd => self.array_end(),
-                    Token::WhiteSpace => Some(Ok(Action::Nothing)),
-                    _ => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                },
-                State::InArrayLastWasDelim => match t {
-                    Token::ArrayBegin => {
-                        self.state = State::InArrayEmpty;
-                        self.stack.push(Stack::Array);
-                        Some(Ok(Action::NewArray))
-                    }
-                    Token::ObjectBegin => {
-                        self.state = State::InObjectEmpty;
-                        self.stack.push(Stack::Object);
-                        Some(Ok(Action::NewObject))
-                    }
-                    Token::False => {
-                        self.state = State::LastWasValueIn(Array);
-                        Some(Ok(Action::Push(Value::False)))
-                    }
-                    Token::True => {
-                        self.state = State::LastWasValueIn(Array);
-                        Some(Ok(Action::Push(Value::True)))
-                    }
-                    Token::Null => {
-                        self.state = State::LastWasValueIn(Array);
-                        Some(Ok(Action::Push(Value::Null)))
-                    }
-                    Token::String(s) => {
-                        self.state = State::LastWasValueIn(Array);
-                        Some(Ok(Action::Push(Value::String(s))))
-                    }
-                    Token::Number(n) => {
-                        self.state = State::LastWasValueIn(Array);
-                        Some(Ok(Action::Push(Value::Number(n))))
-                    }
-                    Token::WhiteSpace => Some(Ok(Action::Nothing)),
-                    Token::ValueSeparator | Token::ArrayEnd => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                    Token::NameSeparator | Token::ObjectEnd => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                },
-                State::InObjectEmpty => match t {
-                    Token::String(s) => {
-                        self.keys += 1;
-                        self.state = State::InObjectLastWasKey;
-                        Some(Ok(Action::NewKey(s)))
-                    }
-                    Token::ObjectEnd => self.object_end(),
-                    Token::WhiteSpace => Some(Ok(Action::Nothing)),
-                    _ => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                },
-                State::InObjectLastWasKey => match t {
-                    Token::NameSeparator => {
-                        self.state = State::InObjectLastWasNameDelim;
-                        Some(Ok(Action::Nothing))
-                    }
-                    Token::WhiteSpace => Some(Ok(Action::Nothing)),
-                    _ => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                },
-                State::InObjectLastWasNameDelim => match t {
-                    Token::ArrayBegin => {
-                        self.state = State::InArrayEmpty;
-                        self.stack.push(Stack::Array);
-                        Some(Ok(Action::NewArray))
-                    }
-                    Token::ObjectBegin => {
-                        self.state = State::InObjectEmpty;
-                        self.stack.push(Stack::Object);
-                        Some(Ok(Action::NewObject))
-                    }
-                    Token::False => {
-                        self.state = State::LastWasValueIn(Object);
-                        self.keys -= 1;
-                        Some(Ok(Action::Push(Value::False)))
-                    }
-                    Token::True => {
-                        self.state = State::LastWasValueIn(Object);
-                        self.keys -= 1;
-                        Some(Ok(Action::Push(Value::True)))
-                    }
-                    Token::Null => {
-                        self.state = State::LastWasValueIn(Object);
-                        self.keys -= 1;
-                        Some(Ok(Action::Push(Value::Null)))
-                    }
-                    Token::String(s) => {
-                        self.state = State::LastWasValueIn(Object);
-                        self.keys -= 1;
-                        Some(Ok(Action::Push(Value::String(s))))
-                    }
-                    Token::Number(n) => {
-                        self.state = State::LastWasValueIn(Object);
-                        self.keys -= 1;
-                        Some(Ok(Action::Push(Value::Number(n))))
-                    }
-                    Token::WhiteSpace => Some(Ok(Action::Nothing)),
+impl Machine {
+    /// Reads the next token.
+    pub(crate) fn step<'a>(&mut self, t: Token<'a>) -> Option<Result<Action<'a>, TokenizeError>> {
+        debug_state!(t, self.state);
+        if let Token::Comment(_) = t {
+            return self.step(Token::WhiteSpace);
+        }
+        let t = match self.extension(t) {
+            Ok(t) => t,
+            Err(r) => return r,
+        };
+        match self.state {
+            State::Begin => match t {
+                Token::ArrayBegin => {
+                    self.state = State::InArrayEmpty;
+                    self.stack.push(Stack::Array);
+                    Some(Ok(Action::NewArray))
+                }
+                Token::ObjectBegin => {
+                    self.state = State::InObjectEmpty;
+                    self.stack.push(Stack::Object);
+                    Some(Ok(Action::NewObject))
+                }
+                Token::False => {
+                    self.state = State::End;
+                    Some(Ok(Action::Push(Value::False)))
+                }
+                Token::True => {
+                    self.state = State::End;
+                    Some(Ok(Action::Push(Value::True)))
+                }
+                Token::Null => {
+                    self.state = State::End;
+                    Some(Ok(Action::Push(Value::Null)))
+                }
+                Token::String(s) => {
+                    self.state = State::End;
+                    Some(Ok(Action::Push(Value::String(s))))
+                }
+                Token::Number(n) => {
+                    self.state = State::End;
+                    Some(Ok(Action::Push(Value::Number(n))))
+                }
+                Token::WhiteSpace => Some(Ok(Action::Nothing)),
+                Token::ArrayEnd
+                | Token::ObjectEnd
+                | Token::NameSeparator
+                | Token::ValueSeparator
+                | Token::Identifier(_)
+                | Token::NonFinite(_)
+                | Token::Comment(_) => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+            },
+            State::InArrayEmpty => match t {
+                Token::ArrayBegin => {
+                    self.state = State::InArrayEmpty;
+                    self.stack.push(Stack::Array);
+                    Some(Ok(Action::NewArray))
+                }
+                Token::ObjectBegin => {
+                    self.state = State::InObjectEmpty;
+                    self.stack.push(Stack::Object);
+                    Some(Ok(Action::NewObject))
+                }
+                Token::False => {
+                    self.state = State::LastWasValueIn(Array);
+                    Some(Ok(Action::Push(Value::False)))
+                }
+                Token::True => {
+                    self.state = State::LastWasValueIn(Array);
+                    Some(Ok(Action::Push(Value::True)))
+                }
+                Token::Null => {
+                    self.state = State::LastWasValueIn(Array);
+                    Some(Ok(Action::Push(Value::Null)))
+                }
+                Token::String(s) => {
+                    self.state = State::LastWasValueIn(Array);
+                    Some(Ok(Action::Push(Value::String(s))))
+                }
+                Token::Number(n) => {
+                    self.state = State::LastWasValueIn(Array);
+                    Some(Ok(Action::Push(Value::Number(n))))
+                }
+                Token::WhiteSpace => Some(Ok(Action::Nothing)),
+                Token::ArrayEnd => self.array_end(),
+                Token::ValueSeparator => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+                Token::NameSeparator
+                | Token::ObjectEnd
+                | Token::Identifier(_)
+                | Token::NonFinite(_)
+                | Token::Comment(_) => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+            },
+            State::LastWasValueIn(Array) => match t {
+                Token::ValueSeparator => {
+                    self.state = State::InArrayLastWasDelim;
+                    Some(Ok(Action::Nothing))
+                }
+                Token::ArrayEnd => self.array_end(),
+                Token::WhiteSpace => Some(Ok(Action::Nothing)),
+                _ => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+            },
+            State::InArrayLastWasDelim => match t {
+                Token::ArrayBegin => {
+                    self.state = State::InArrayEmpty;
+                    self.stack.push(Stack::Array);
+                    Some(Ok(Action::NewArray))
+                }
+                Token::ObjectBegin => {
+                    self.state = State::InObjectEmpty;
+                    self.stack.push(Stack::Object);
+                    Some(Ok(Action::NewObject))
+                }
+                Token::False => {
+                    self.state = State::LastWasValueIn(Array);
+                    Some(Ok(Action::Push(Value::False)))
+                }
+                Token::True => {
+                    self.state = State::LastWasValueIn(Array);
+                    Some(Ok(Action::Push(Value::True)))
+                }
+                Token::Null => {
+                    self.state = State::LastWasValueIn(Array);
+                    Some(Ok(Action::Push(Value::Null)))
+                }
+                Token::String(s) => {
+                    self.state = State::LastWasValueIn(Array);
+                    Some(Ok(Action::Push(Value::String(s))))
+                }
+                Token::Number(n) => {
+                    self.state = State::LastWasValueIn(Array);
+                    Some(Ok(Action::Push(Value::Number(n))))
+                }
+                Token::WhiteSpace => Some(Ok(Action::Nothing)),
+                Token::ValueSeparator | Token::ArrayEnd => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+                Token::NameSeparator
+                | Token::ObjectEnd
+                | Token::Identifier(_)
+                | Token::NonFinite(_)
+                | Token::Comment(_) => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+            },
+            State::InObjectEmpty => match t {
+                Token::String(s) => {
+                    self.keys += 1;
+                    self.state = State::InObjectLastWasKey;
+                    Some(Ok(Action::NewKey(s)))
+                }
+                Token::ObjectEnd => self.object_end(),
+                Token::WhiteSpace => Some(Ok(Action::Nothing)),
+                _ => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+            },
+            State::InObjectLastWasKey => match t {
+                Token::NameSeparator => {
+                    self.state = State::InObjectLastWasNameDelim;
+                    Some(Ok(Action::Nothing))
+                }
+                Token::WhiteSpace => Some(Ok(Action::Nothing)),
+                _ => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+            },
+            State::InObjectLastWasNameDelim => match t {
+                Token::ArrayBegin => {
+                    self.state = State::InArrayEmpty;
+                    self.stack.push(Stack::Array);
+                    Some(Ok(Action::NewArray))
+                }
+                Token::ObjectBegin => {
+                    self.state = State::InObjectEmpty;
+                    self.stack.push(Stack::Object);
+                    Some(Ok(Action::NewObject))
+                }
+                Token::False => {
+                    self.state = State::LastWasValueIn(Object);
+                    self.keys -= 1;
+                    Some(Ok(Action::Push(Value::False)))
+                }
+                Token::True => {
+                    self.state = State::LastWasValueIn(Object);
+                    self.keys -= 1;
+                    Some(Ok(Action::Push(Value::True)))
+                }
+                Token::Null => {
+                    self.state = State::LastWasValueIn(Object);
+                    self.keys -= 1;
+                    Some(Ok(Action::Push(Value::Null)))
+                }
+                Token::String(s) => {
+                    self.state = State::LastWasValueIn(Object);
+                    self.keys -= 1;
+                    Some(Ok(Action::Push(Value::String(s))))
+                }
+                Token::Number(n) => {
+                    self.state = State::LastWasValueIn(Object);
+                    self.keys -= 1;
+                    Some(Ok(Action::Push(Value::Number(n))))
+                }
+                Token::WhiteSpace => Some(Ok(Action::Nothing)),
 
-                    Token::ValueSeparator | Token::ArrayEnd => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                    Token::NameSeparator | Token::ObjectEnd => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                },
-                State::LastWasValueIn(Object) => match t {
-                    Token::ValueSeparator => {
-                        self.state = State::InObjectLastWasDelim;
-                        Some(Ok(Action::Nothing))
-                    }
-                    Token::ObjectEnd => self.object_end(),
-                    Token::WhiteSpace => Some(Ok(Action::Nothing)),
-                    _ => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                },
-                State::InObjectLastWasDelim => match t {
-                    Token::String(s) => {
-                        self.keys += 1;
-                        self.state = State::InObjectLastWasKey;
-                        Some(Ok(Action::NewKey(s)))
-                    }
-                    Token::WhiteSpace => Some(Ok(Action::Nothing)),
-                    _ => {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::UnexpectedToken(t)))
-                    }
-                },
-                State::End => {
+                Token::ValueSeparator | Token::ArrayEnd => {
                     self.state = State::Ended;
-                    match t {
-                        Token::WhiteSpace => match &self.lexer.report() {
-                            Some(Ok(())) => Some(Ok(Action::TheEnd)),
-                            Some(Err(e)) => Some(Err(e.clone())),
-                            None => {
-                                if self.lexer.next().is_none() {
-                                    Some(Ok(Action::TheEnd))
-                                } else {
-                                    Some(Err(TokenizeError::InputTooLong))
-                                }
-                            }
-                        },
-                        _ => {
-                            self.state = State::Ended;
-                            Some(Err(TokenizeError::InputTooLong))
-                        }
-                    }
-                }
-                State::Ended => None,
-            }
-        } else {
-            match self.state {
-                State::End => {
-                    if matches!(self.lexer.report(), Some(Ok(())))
-                        && self.keys == 0
-                        && self.stack.is_empty()
-                    {
-                        self.state = State::Ended;
-                        Some(Ok(Action::TheEnd))
-                    } else {
-                        self.state = State::Ended;
-                        Some(Err(TokenizeError::InputTooLong))
-                    }
-                }
-                State::Ended => None,
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+                Token::NameSeparator
+                | Token::ObjectEnd
+                | Token::Identifier(_)
+                | Token::NonFinite(_)
+                | Token::Comment(_) => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+            },
+            State::LastWasValueIn(Object) => match t {
+                Token::ValueSeparator => {
+                    self.state = State::InObjectLastWasDelim;
+                    Some(Ok(Action::Nothing))
+                }
+                Token::ObjectEnd => self.object_end(),
+                Token::WhiteSpace => Some(Ok(Action::Nothing)),
+                _ => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+            },
+            State::InObjectLastWasDelim => match t {
+                Token::String(s) => {
+                    self.keys += 1;
+                    self.state = State::InObjectLastWasKey;
+                    Some(Ok(Action::NewKey(s)))
+                }
+                Token::WhiteSpace => Some(Ok(Action::Nothing)),
+                _ => {
+                    self.state = State::Ended;
+                    Some(Err(TokenizeError::UnexpectedToken(t.into_owned())))
+                }
+            },
+            State::End => match t {
+                Token::WhiteSpace => Some(Ok(Action::Nothing)),
                 _ => {
                     self.state = State::Ended;
                     Some(Err(TokenizeError::InputTooLong))
                 }
+            },
+            State::Ended => None,
+        }
+    }
+
+    /// Handles the tokens which are only valid in a dialect, giving back the other ones.
+    fn extension<'a>(&mut self, t: Token<'a>) -> Result<Token<'a>, Option<Result<Action<'a>, TokenizeError>>> {
+        if self.dialect == Dialect::Json {
+            return Ok(t);
+        }
+        let expects_key = matches!(self.state, State::InObjectEmpty | State::InObjectLastWasDelim);
+        let expects_value = matches!(
+            self.state,
+            State::Begin
+                | State::InArrayEmpty
+                | State::InArrayLastWasDelim
+                | State::InObjectLastWasNameDelim
+        );
+        match t {
+            // Trailing commas
+            Token::ArrayEnd if matches!(self.state, State::InArrayLastWasDelim) => Err(self.array_end()),
+            Token::ObjectEnd if matches!(self.state, State::InObjectLastWasDelim) => Err(self.object_end()),
+            t if self.dialect != Dialect::Json5 => Ok(t),
+            // Any identifier can be a key, the literals included
+            Token::Identifier(s) if expects_key => Ok(Token::String(s.into())),
+            Token::True if expects_key => Ok(Token::String("true".into())),
+            Token::False if expects_key => Ok(Token::String("false".into())),
+            Token::Null if expects_key => Ok(Token::String("null".into())),
+            Token::Identifier(s) if expects_value && s == "Infinity" => {
+                Err(self.push(Value::NonFinite(NonFinite::Infinity)))
+            }
+            Token::Identifier(s) if expects_value && s == "NaN" => Err(self.push(Value::NonFinite(NonFinite::NaN))),
+            Token::NonFinite(n) if expects_value => Err(self.push(Value::NonFinite(n))),
+            t => Ok(t),
+        }
+    }
+
+    /// Pushes a value read where one was expected.
+    fn push<'a>(&mut self, v: Value<'a>) -> Option<Result<Action<'a>, TokenizeError>> {
+        match self.state {
+            State::Begin => self.state = State::End,
+            State::InArrayEmpty | State::InArrayLastWasDelim => self.state = State::LastWasValueIn(Array),
+            State::InObjectLastWasNameDelim => {
+                self.state = State::LastWasValueIn(Object);
+                self.keys -= 1;
+            }
+            _ => {
+                self.state = State::Ended;
+                return Some(Err(internal_error!())); //COV_IGNORE
+            }
+        }
+        Some(Ok(Action::Push(v)))
+    }
+
+    /// Handles a lexical error: the automaton ends with it.
+    pub(crate) fn fail<'a>(&mut self, e: TokenizeError) -> Option<Result<Action<'a>, TokenizeError>> {
+        match self.state {
+            State::Ended => None,
+            _ => {
+                self.state = State::Ended;
+                Some(Err(e))
+            }
+        }
+    }
+
+    /// Handles the end of the input.
+    ///
+    /// It fails with [`TokenizeError::InputEndedEarly`] if the value isn't complete.
+    pub(crate) fn end<'a>(&mut self) -> Option<Result<Action<'a>, TokenizeError>> {
+        match self.state {
+            State::End if self.keys == 0 && self.stack.is_empty() => {
+                self.state = State::Ended;
+                Some(Ok(Action::TheEnd))
+            }
+            State::Ended => None,
+            _ => {
+                self.state = State::Ended;
+                Some(Err(TokenizeError::InputEndedEarly))
             }
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 /// Which action should a parser do at each step of the automaton
-pub enum Action {
+pub enum Action<'a> {
     /// Nothing to be done
     Nothing,
     /// A new array is to be created
@@ -362,9 +512,9 @@ pub enum Action {
     /// A new object is to be created
     NewObject,
     /// A new key was read
-    NewKey(String),
+    NewKey(JsonString<'a>),
     /// A value is to be pushed to the last array/object (if none it's because the Value is the value of the whole JSON document)
-    Push(Value),
+    Push(Value<'a>),
     /// The last array/object ended and is to be pushed
     Close,
     /// The parsing ended
@@ -391,3 +541,86 @@ enum State {
     End,
     Ended,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chars::Chars, lexer_bytes::LexerBytes, lexer_iter::LexerIter};
+
+    #[test]
+    fn token_span() {
+        let mut automaton = Automaton::new(LexerIter::new(Chars::from("{\"a\": 1,\n \"b\" 23}")));
+        let end = automaton.by_ref().find(Result::is_err);
+        assert!(matches!(end, Some(Err(TokenizeError::UnexpectedToken(_)))));
+        let Span { start, end } = automaton.token_span();
+        assert_eq!((14, 2, 6), (start.offset, start.line, start.column));
+        assert_eq!((16, 2, 8), (end.offset, end.line, end.column));
+    }
+
+    #[test]
+    fn end_errors() {
+        let last = |s| Automaton::new(LexerIter::new(Chars::from(s))).last();
+        assert_eq!(Some(Ok(Action::TheEnd)), last("[1] "));
+        assert_eq!(Some(Err(TokenizeError::InputEndedEarly)), last(""));
+        assert_eq!(Some(Err(TokenizeError::InputEndedEarly)), last("{\"a\": [1, "));
+        assert_eq!(Some(Err(TokenizeError::InputTooLong)), last("[1] 2"));
+        assert_eq!(Some(Err(TokenizeError::UnkownToken)), last("[1] x"));
+        assert_eq!(Some(Err(TokenizeError::ExpectedADigit)), last("[1, -]"));
+    }
+
+    #[test]
+    fn borrowed_strings() {
+        let input = String::from(r#"{"key": ["value"]}"#);
+        let actions: Vec<Action> = Automaton::new(LexerBytes::from(input.as_str()))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        match (&actions[1], &actions[5]) {
+            (
+                Action::NewKey(JsonString::Text(Cow::Borrowed(k))),
+                Action::Push(Value::String(JsonString::Text(Cow::Borrowed(v)))),
+            ) => {
+                assert_eq!(("key", "value"), (*k, *v));
+                assert!(input.as_bytes().as_ptr_range().contains(&v.as_ptr()));
+            }
+            _ => unreachable!("{:?}", actions),
+        }
+    }
+
+    #[test]
+    fn jsonc() {
+        let last = |s, dialect| Automaton::new(LexerBytes::from(s).with_dialect(dialect)).last();
+        for jsonc in ["// c\n{\"a\": [1, 2,], /* b */ \"b\": {\"c\": 1,},}", "[/**/]", "1 //"] {
+            assert!(matches!(last(jsonc, Dialect::Json), Some(Err(_))), "{}", jsonc);
+            assert_eq!(Some(Ok(Action::TheEnd)), last(jsonc, Dialect::Jsonc), "{}", jsonc);
+        }
+        for invalid in ["[1,,]", "[,]", "{,}", "{a: 1}", "['a']", "[Infinity]", "/* */"] {
+            assert!(matches!(last(invalid, Dialect::Jsonc), Some(Err(_))), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn attached_comments() {
+        let input = "/* doc */ {// key\n\"a\": /* value */ 1, \"b\": 2 /* end */}";
+        let mut automaton = Automaton::new(LexerBytes::from(input).with_dialect(Dialect::Jsonc).keep_comments());
+        let mut attached = Vec::new();
+        while let Some(a) = automaton.next() {
+            match a.unwrap() {
+                Action::Nothing => {}
+                a => attached.push((a, automaton.take_comments())),
+            }
+        }
+        let comments: Vec<Vec<Cow<str>>> = attached.into_iter().map(|(_, c)| c).collect();
+        assert_eq!(
+            vec![
+                vec!["/* doc */"],
+                vec!["// key"],
+                vec!["/* value */"],
+                vec![],
+                vec![],
+                vec!["/* end */"],
+                vec![],
+            ],
+            comments
+        );
+    }
+}
@@ -1,90 +1,536 @@
 use std::char::from_u32;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read};
 
-use crate::err::TokenizeError;
+use crate::err::{internal_error, TokenizeError, Utf8Error};
+
+/// Size of the chunks read by [`Chars::from_reader`].
+const READ_BUFFER_SIZE: usize = 8 * 1024;
+
+/// A source of bytes, which can fail like a [`Read`].
+pub trait ByteSource {
+    /// Returns the next byte, `Ok(None)` once the input is over.
+    ///
+    /// # Errors
+    /// Returns the error which prevented the byte to be read.
+    fn next_byte(&mut self) -> io::Result<Option<u8>>;
+}
+
+impl<I: Iterator<Item = u8>> ByteSource for I {
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.next())
+    }
+}
+
+impl ByteSource for Box<dyn ByteSource + '_> {
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        (**self).next_byte()
+    }
+}
+
+/// A [`ByteSource`] reading a [`Read`] by chunks.
+pub struct ReadBytes<R> {
+    reader: R,
+    buffer: Box<[u8]>,
+    pos: usize,
+    len: usize,
+}
+
+impl<R: Read> ReadBytes<R> {
+    /// Create a new one
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: vec![0u8; READ_BUFFER_SIZE].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+        }
+    }
+}
+
+impl<R: Read> ByteSource for ReadBytes<R> {
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        while self.pos == self.len {
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => return Ok(None),
+                Ok(len) => {
+                    self.pos = 0;
+                    self.len = len;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.pos += 1;
+        Ok(Some(self.buffer[self.pos - 1]))
+    }
+}
+
+/// A location in the input.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    /// Number of bytes read before this position
+    pub offset: usize,
+    /// Line number, starting at 1 (only `'\n'` starts a new line)
+    pub line: usize,
+    /// Column number, starting at 1 and counted in chars
+    pub column: usize,
+}
+
+impl Position {
+    /// Moves the position past the char `c`, which was encoded with `len` bytes.
+    pub(crate) fn advance(&mut self, c: char, len: usize) {
+        self.offset += len;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+impl Position {
+    /// Returns the position `rel`, given relatively to `self`.
+    pub(crate) fn after(self, rel: Position) -> Position {
+        Position {
+            offset: self.offset + rel.offset,
+            line: self.line + rel.line - 1,
+            column: if rel.line == 1 {
+                self.column + rel.column - 1
+            } else {
+                rel.column
+            },
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{} (byte {})", self.line, self.column, self.offset)
+    }
+}
+
+/// The encodings a JSON text can be read from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Encoding {
+    /// UTF-8, the only encoding allowed by RFC 8259 for exchanged JSON
+    Utf8,
+    /// UTF-16, little endian
+    Utf16Le,
+    /// UTF-16, big endian
+    Utf16Be,
+    /// UTF-32, little endian
+    Utf32Le,
+    /// UTF-32, big endian
+    Utf32Be,
+}
+
+impl Encoding {
+    /// Returns the byte order mark of the encoding.
+    #[must_use]
+    pub fn bom(self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Encoding::Utf16Le => &[0xFF, 0xFE],
+            Encoding::Utf16Be => &[0xFE, 0xFF],
+            Encoding::Utf32Le => &[0xFF, 0xFE, 0x00, 0x00],
+            Encoding::Utf32Be => &[0x00, 0x00, 0xFE, 0xFF],
+        }
+    }
+}
+
+/// What to do with a byte order mark at the beginning of a UTF-8 input.
+///
+/// The byte order marks of UTF-16 and UTF-32 inputs are always skipped.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BomPolicy {
+    /// Skip it silently
+    Skip,
+    /// Skip it, and remember it was there (see [`Chars::had_bom`])
+    Report,
+    /// Fail with [`TokenizeError::ByteOrderMark`]
+    Reject,
+}
 
 /// An iterator for bufferized char reading
-pub struct Chars {
-    inner: Box<dyn Iterator<Item = u8>>,
+pub struct Chars<S> {
+    inner: S,
     pub(crate) status: Option<Result<(), TokenizeError>>,
+    /// Position of the next char to be returned by `next`
+    position: Position,
+    peeked: Option<Option<(char, usize)>>,
+    /// Bytes already read from `inner` but not decoded yet
+    pending: VecDeque<u8>,
+    /// The encoding of the input, `None` until it is sniffed
+    encoding: Option<Encoding>,
+    sniffed: bool,
+    bom_policy: BomPolicy,
+    bom: bool,
+    /// Number of bytes read by `next_byte`
+    consumed: usize,
+    lossy: bool,
+    substitutions: Vec<Position>,
 }
 
-impl Chars {
-    /// Create a new one
-    pub fn new(inner: Box<dyn Iterator<Item = u8>>) -> Self {
+/// [`Chars`] over a boxed source, when the type of the source can't be named.
+pub type BoxedChars<'a> = Chars<Box<dyn ByteSource + 'a>>;
+
+impl<R: Read> Chars<ReadBytes<R>> {
+    /// Create a new one reading `reader` by chunks.
+    ///
+    /// The errors of `reader` end the input, and are reported as [`TokenizeError::Io`].
+    pub fn from_reader(reader: R) -> Self {
+        Self::new(ReadBytes::new(reader))
+    }
+}
+
+impl<'a> BoxedChars<'a> {
+    /// Create a new one from any [`ByteSource`], hiding its type.
+    pub fn boxed(inner: impl ByteSource + 'a) -> Self {
+        Self::new(Box::new(inner))
+    }
+}
+
+impl<S: ByteSource> Chars<S> {
+    /// Create a new one from any [`ByteSource`], an iterator of bytes for example.
+    pub fn new(inner: S) -> Self {
         Self {
             inner,
             status: None,
+            position: Position::default(),
+            peeked: None,
+            pending: VecDeque::with_capacity(4),
+            encoding: None,
+            sniffed: false,
+            bom_policy: BomPolicy::Reject,
+            bom: false,
+            consumed: 0,
+            lossy: false,
+            substitutions: Vec::new(),
         }
     }
-}
 
-impl Iterator for Chars {
-    type Item = char;
+    /// Reads the input with the given `encoding` instead of guessing it from the first bytes.
+    ///
+    /// A byte order mark is still skipped if it matches `encoding`.
+    #[must_use]
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.inner.next() {
-            None => {
+    /// Sets what to do with a UTF-8 byte order mark (rejected by default).
+    #[must_use]
+    pub fn with_bom_policy(mut self, bom_policy: BomPolicy) -> Self {
+        self.bom_policy = bom_policy;
+        self
+    }
+
+    /// Replaces the ill-formed sequences with U+FFFD instead of failing.
+    ///
+    /// Each maximal subpart of an ill-formed sequence is replaced by one U+FFFD, which is the
+    /// practice recommended by the Unicode Standard (and the one of `String::from_utf8_lossy`).
+    /// The replacements are listed by [`Chars::substitutions`].
+    #[must_use]
+    pub fn lossy(mut self) -> Self {
+        self.lossy = true;
+        self
+    }
+
+    /// Returns the positions where an ill-formed sequence was replaced with U+FFFD.
+    #[must_use]
+    pub fn substitutions(&self) -> &[Position] {
+        &self.substitutions
+    }
+
+    /// Reads the input as the continuation of a UTF-8 text: the encoding isn't guessed and
+    /// there is no byte order mark to look for.
+    #[must_use]
+    pub(crate) fn resumed(mut self) -> Self {
+        self.encoding = Some(Encoding::Utf8);
+        self.sniffed = true;
+        self
+    }
+
+    /// Returns the encoding of the input, if it is already known.
+    #[must_use]
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    /// Whether a byte order mark was skipped at the beginning of the input.
+    ///
+    /// A UTF-8 byte order mark is only remembered with [`BomPolicy::Report`].
+    #[must_use]
+    pub fn had_bom(&self) -> bool {
+        self.bom
+    }
+
+    /// Returns the position of the next char to be read.
+    #[must_use]
+    pub fn current_position(&self) -> Position {
+        self.position
+    }
+
+    /// Returns a reference to the next char without consuming it.
+    pub fn peek(&mut self) -> Option<&char> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.decode());
+        }
+        match &self.peeked {
+            Some(Some((c, _))) => Some(c),
+            _ => None,
+        }
+    }
+
+    fn next_byte(&mut self) -> Result<Option<u8>, TokenizeError> {
+        let b = match self.pending.pop_front() {
+            Some(b) => Some(b),
+            None => self.inner.next_byte()?,
+        };
+        if b.is_some() {
+            self.consumed += 1;
+        }
+        Ok(b)
+    }
+
+    /// Puts back a byte read by `next_byte`.
+    fn unread(&mut self, b: u8) {
+        self.consumed -= 1;
+        self.pending.push_front(b);
+    }
+
+    /// Guesses the encoding from the first bytes (unless it was given) and skips the byte order mark.
+    ///
+    /// Without a byte order mark, the encoding is found the way RFC 4627 (section 3) describes
+    /// it: the first two chars of a JSON text are ASCII, so the null bytes give the encoding away.
+    fn sniff(&mut self) -> Result<(), TokenizeError> {
+        while self.pending.len() < 4 {
+            match self.inner.next_byte()? {
+                Some(b) => self.pending.push_back(b),
+                None => break,
+            }
+        }
+        let mut head = [None; 4];
+        for (h, b) in head.iter_mut().zip(self.pending.iter()) {
+            *h = Some(*b);
+        }
+        let encoding = self.encoding.unwrap_or(match head {
+            [Some(0xEF), Some(0xBB), Some(0xBF), _] => Encoding::Utf8,
+            [Some(0x00), Some(0x00), Some(0xFE), Some(0xFF)] => Encoding::Utf32Be,
+            [Some(0xFF), Some(0xFE), Some(0x00), Some(0x00)] => Encoding::Utf32Le,
+            [Some(0xFE), Some(0xFF), _, _] => Encoding::Utf16Be,
+            [Some(0xFF), Some(0xFE), _, _] => Encoding::Utf16Le,
+            [Some(0x00), Some(0x00), Some(0x00), Some(_)] => Encoding::Utf32Be,
+            [Some(_), Some(0x00), Some(0x00), Some(0x00)] => Encoding::Utf32Le,
+            [Some(0x00), Some(_), _, _] => Encoding::Utf16Be,
+            [Some(_), Some(0x00), _, _] => Encoding::Utf16Le,
+            _ => Encoding::Utf8,
+        });
+        self.encoding = Some(encoding);
+        let bom = encoding.bom();
+        if self.pending.len() >= bom.len() && self.pending.iter().zip(bom).all(|(a, b)| a == b) {
+            if encoding == Encoding::Utf8 {
+                match self.bom_policy {
+                    BomPolicy::Skip => {}
+                    BomPolicy::Report => self.bom = true,
+                    BomPolicy::Reject => return Err(TokenizeError::ByteOrderMark),
+                }
+            } else {
+                self.bom = true;
+            }
+            self.pending.drain(..bom.len());
+            self.position.offset += bom.len();
+        }
+        Ok(())
+    }
+
+    /// Decodes the next char from the bytes, along with its length in bytes.
+    fn decode(&mut self) -> Option<(char, usize)> {
+        if self.status.is_some() {
+            return None;
+        }
+        if !self.sniffed {
+            self.sniffed = true;
+            if let Err(e) = self.sniff() {
+                self.status = Some(Err(e));
+                return None;
+            }
+        }
+        let consumed = self.consumed;
+        let decoded = match self.encoding {
+            Some(Encoding::Utf8) | None => self.decode_utf8(),
+            Some(Encoding::Utf16Le) => self.decode_utf16(u16::from_le_bytes),
+            Some(Encoding::Utf16Be) => self.decode_utf16(u16::from_be_bytes),
+            Some(Encoding::Utf32Le) => self.decode_utf32(u32::from_le_bytes),
+            Some(Encoding::Utf32Be) => self.decode_utf32(u32::from_be_bytes),
+        };
+        match decoded {
+            Ok(Some(c)) => Some(c),
+            Err(TokenizeError::InvalidUTF8(_) | TokenizeError::InvalidUTF16 | TokenizeError::InvalidUTF32)
+                if self.lossy =>
+            {
+                self.substitutions.push(self.position);
+                Some((char::REPLACEMENT_CHARACTER, self.consumed - consumed))
+            }
+            Ok(None) => {
                 self.status = Some(Ok(()));
                 None
             }
-            Some(n) => {
-                if n == 0u8 {
-                    Some('\0')
-                } else if n >> 7 == 0 {
-                    match char::from_u32(n as u32) {
-                        Some(c) => Some(c),
-                        None => {
-                            self.status = Some(Err(TokenizeError::InvalidUTF8));
-                            None
-                        }
-                    }
-                } else {
-                    let (k, mut c) = if n >> 5 == 0b110 {
-                        (1, (n & !(0b110 << 5)) as u32)
-                    } else if n >> 4 == 0b1110 {
-                        (2, (n & !(0b1110 << 4)) as u32)
-                    } else if n >> 3 == 0b11110 {
-                        (3, (n & !(0b11110 << 3)) as u32)
-                    } else {
-                        self.status = Some(Err(TokenizeError::InvalidUTF8));
-                        return None;
+            Err(e) => {
+                self.status = Some(Err(e));
+                None
+            }
+        }
+    }
+
+    /// Decodes a UTF-8 sequence, rejecting every ill-formed one (see table 3-7 of the Unicode
+    /// Standard, "Well-Formed UTF-8 Byte Sequences").
+    ///
+    /// A byte which can't continue the sequence is left to be read again.
+    fn decode_utf8(&mut self) -> Result<Option<(char, usize)>, TokenizeError> {
+        let lead = match self.next_byte()? {
+            Some(n) => n,
+            None => return Ok(None),
+        };
+        let (len, mut c, second) = match lead {
+            0x00..=0x7F => return Ok(Some((char::from(lead), 1))),
+            0x80..=0xBF => return Err(Utf8Error::UnexpectedContinuationByte.into()),
+            0xC0 | 0xC1 => return Err(Utf8Error::Overlong.into()),
+            0xC2..=0xDF => (2, u32::from(lead & 0x1F), 0x80..=0xBF),
+            0xE0 => (3, u32::from(lead & 0x0F), 0xA0..=0xBF),
+            0xE1..=0xEC | 0xEE..=0xEF => (3, u32::from(lead & 0x0F), 0x80..=0xBF),
+            0xED => (3, u32::from(lead & 0x0F), 0x80..=0x9F),
+            0xF0 => (4, u32::from(lead & 0x07), 0x90..=0xBF),
+            0xF1..=0xF3 => (4, u32::from(lead & 0x07), 0x80..=0xBF),
+            0xF4 => (4, u32::from(lead & 0x07), 0x80..=0x8F),
+            0xF5..=0xFF => return Err(Utf8Error::OutOfRange.into()),
+        };
+        for i in 1..len {
+            let expected = if i == 1 { second.clone() } else { 0x80..=0xBF };
+            match self.next_byte()? {
+                Some(n) if expected.contains(&n) => {
+                    c = (c << 6) | u32::from(n & 0x3F);
+                }
+                Some(n) => {
+                    self.unread(n);
+                    let e = match (lead, n) {
+                        (_, 0x00..=0x7F | 0xC0..=0xFF) => Utf8Error::MissingContinuationByte,
+                        (0xE0 | 0xF0, _) if i == 1 => Utf8Error::Overlong,
+                        (0xED, _) if i == 1 => Utf8Error::Surrogate,
+                        (0xF4, _) if i == 1 => Utf8Error::OutOfRange,
+                        _ => return Err(internal_error!()),
                     };
-                    for _ in 1..=k {
-                        let n = self.inner.next()?;
-                        if n & (0b10 << 6) == (0b10 << 6) {
-                            c <<= 6;
-                            c |= (n & !(0b10 << 6)) as u32;
-                        } else {
-                            self.status = Some(Err(TokenizeError::InvalidUTF8));
-                            return None;
-                        }
-                    }
-                    match from_u32(c) {
-                        Some(c) => Some(c),
-                        None => {
-                            self.status = Some(Err(TokenizeError::InvalidUTF8));
-                            None
-                        }
-                    }
+                    return Err(e.into());
                 }
+                None => return Err(Utf8Error::Truncated.into()),
+            }
+        }
+        match from_u32(c) {
+            Some(c) => Ok(Some((c, len))),
+            None => Err(internal_error!()),
+        }
+    }
+
+    /// Reads the two bytes of a UTF-16 code unit, `Ok(None)` if the input ended before it.
+    fn read_u16(&mut self) -> Result<Option<[u8; 2]>, TokenizeError> {
+        match (self.next_byte()?, self.next_byte()?) {
+            (Some(a), Some(b)) => Ok(Some([a, b])),
+            (None, _) => Ok(None),
+            (Some(_), None) => Err(TokenizeError::InvalidUTF16),
+        }
+    }
+
+    /// Decodes a UTF-16 code unit or surrogate pair.
+    ///
+    /// A code unit which doesn't complete a surrogate pair is left to be read again.
+    fn decode_utf16(&mut self, from_bytes: fn([u8; 2]) -> u16) -> Result<Option<(char, usize)>, TokenizeError> {
+        let hi = match self.read_u16()? {
+            Some(u) => from_bytes(u),
+            None => return Ok(None),
+        };
+        match hi {
+            0xD800..=0xDBFF => match self.read_u16()? {
+                Some(u) if (0xDC00..=0xDFFF).contains(&from_bytes(u)) => {
+                    let lo = from_bytes(u);
+                    let code = 0x1_0000 + ((u32::from(hi) & 0x03FF) << 10) + (u32::from(lo) & 0x03FF);
+                    from_u32(code)
+                        .map(|c| Some((c, 4)))
+                        .ok_or(TokenizeError::InvalidUTF16)
+                }
+                Some([a, b]) => {
+                    self.unread(b);
+                    self.unread(a);
+                    Err(TokenizeError::InvalidUTF16)
+                }
+                None => Err(TokenizeError::InvalidUTF16),
+            },
+            0xDC00..=0xDFFF => Err(TokenizeError::InvalidUTF16),
+            _ => from_u32(u32::from(hi))
+                .map(|c| Some((c, 2)))
+                .ok_or(TokenizeError::InvalidUTF16),
+        }
+    }
+
+    fn decode_utf32(&mut self, from_bytes: fn([u8; 4]) -> u32) -> Result<Option<(char, usize)>, TokenizeError> {
+        let mut bytes = [0u8; 4];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            match self.next_byte()? {
+                Some(n) => *b = n,
+                None if i == 0 => return Ok(None),
+                None => return Err(TokenizeError::InvalidUTF32),
             }
         }
+        from_u32(from_bytes(bytes))
+            .map(|c| Some((c, 4)))
+            .ok_or(TokenizeError::InvalidUTF32)
+    }
+}
+
+impl<S: ByteSource> Iterator for Chars<S> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (c, len) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.decode(),
+        }?;
+        self.position.advance(c, len);
+        Some(c)
     }
 }
 
-impl From<&str> for Chars {
-    fn from(x: &str) -> Self {
-        #[allow(clippy::needless_collect)]
-        let b: Vec<u8> = x.bytes().collect();
-        Self::new(Box::new(b.into_iter()))
+impl<'a> From<&'a str> for Chars<std::iter::Copied<std::slice::Iter<'a, u8>>> {
+    fn from(x: &'a str) -> Self {
+        Self::new(x.as_bytes().iter().copied()).with_encoding(Encoding::Utf8)
     }
 }
 
-impl From<String> for Chars {
+impl From<String> for Chars<std::vec::IntoIter<u8>> {
     fn from(x: String) -> Self {
-        #[allow(clippy::needless_collect)]
-        let b: Vec<u8> = x.bytes().collect();
-        Self::new(Box::new(b.into_iter()))
+        Self::new(x.into_bytes().into_iter()).with_encoding(Encoding::Utf8)
+    }
+}
+
+/// Returns the error met decoding the first char of `bytes` as UTF-8, if any.
+pub(crate) fn utf8_error(bytes: &[u8]) -> Option<TokenizeError> {
+    let mut chars = Chars::new(bytes.iter().take(4).copied()).resumed();
+    chars.next();
+    match chars.status {
+        Some(Err(e)) => Some(e),
+        _ => None,
     }
 }
 
@@ -94,37 +540,356 @@ mod tests {
 
     #[test]
     fn ascii() {
-        let s: Chars = "\0abcd".into();
+        let s: Chars<_> = "\0abcd".into();
         let t = "\0abcd".chars();
         assert!(s.eq(t));
     }
 
     #[test]
     fn two_bytes() {
-        let s: Chars = "é".into();
+        let s: Chars<_> = "é".into();
         let t = "é".chars();
         assert!(s.eq(t));
     }
 
     #[test]
     fn three_bytes() {
-        let s: Chars = "€".into();
+        let s: Chars<_> = "€".into();
         let t = "€".chars();
         assert!(s.eq(t));
     }
 
     #[test]
     fn four_bytes() {
-        let s: Chars = "a𝄞a".into();
-        let s2: Chars = "a𝄞a".into();
+        let s: Chars<_> = "a𝄞a".into();
         let t = "a𝄞a".chars();
-        let t2 = "a𝄞a".chars();
+        assert!(s.eq(t));
+    }
+
+    #[test]
+    fn position() {
+        let mut s: Chars<_> = "é\n€ a".into();
+        assert_eq!(Position { offset: 0, line: 1, column: 1 }, s.current_position());
+        assert_eq!(Some('é'), s.next());
+        assert_eq!(Position { offset: 2, line: 1, column: 2 }, s.current_position());
+        assert_eq!(Some('\n'), s.next());
+        assert_eq!(Position { offset: 3, line: 2, column: 1 }, s.current_position());
+        assert_eq!(Some('€'), s.next());
+        assert_eq!(Position { offset: 6, line: 2, column: 2 }, s.current_position());
+        s.next();
+        s.next();
+        assert_eq!(None, s.next());
+        assert_eq!(Position { offset: 8, line: 2, column: 4 }, s.current_position());
+    }
+
+    #[test]
+    fn peek_does_not_move() {
+        let mut s: Chars<_> = "ab".into();
+        assert_eq!(Some(&'a'), s.peek());
+        assert_eq!(Some(&'a'), s.peek());
+        assert_eq!(0, s.current_position().offset);
+        assert_eq!(Some('a'), s.next());
+        assert_eq!(1, s.current_position().offset);
+        assert_eq!(Some(&'b'), s.peek());
+        assert_eq!(1, s.current_position().offset);
+    }
+
+    fn utf16(s: &str, to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        s.encode_utf16().flat_map(to_bytes).collect()
+    }
+
+    fn utf32(s: &str, to_bytes: fn(u32) -> [u8; 4]) -> Vec<u8> {
+        s.chars().flat_map(|c| to_bytes(c as u32)).collect()
+    }
+
+    #[test]
+    fn sniff_without_bom() {
+        let text = "[\"a𝄞é\"]";
+        let cases = vec![
+            (text.as_bytes().to_vec(), Encoding::Utf8),
+            (utf16(text, u16::to_le_bytes), Encoding::Utf16Le),
+            (utf16(text, u16::to_be_bytes), Encoding::Utf16Be),
+            (utf32(text, u32::to_le_bytes), Encoding::Utf32Le),
+            (utf32(text, u32::to_be_bytes), Encoding::Utf32Be),
+        ];
+        for (bytes, encoding) in cases {
+            let mut s = Chars::new(bytes.into_iter());
+            let r: String = s.by_ref().collect();
+            assert_eq!(text, r);
+            assert_eq!(Some(encoding), s.encoding());
+            assert_eq!(Some(Ok(())), s.status);
+            assert!(!s.had_bom());
+        }
+    }
+
+    #[test]
+    fn sniff_short_inputs() {
+        let s = Chars::new(vec![b'1'].into_iter());
+        assert_eq!("1", s.collect::<String>());
+        let s = Chars::new(utf16("1", u16::to_le_bytes).into_iter());
+        assert_eq!("1", s.collect::<String>());
+        let s = Chars::new(utf16("1", u16::to_be_bytes).into_iter());
+        assert_eq!("1", s.collect::<String>());
+    }
+
+    #[test]
+    fn skip_bom() {
+        let text = "{}";
+        let cases = vec![
+            (utf16(text, u16::to_le_bytes), Encoding::Utf16Le),
+            (utf16(text, u16::to_be_bytes), Encoding::Utf16Be),
+            (utf32(text, u32::to_le_bytes), Encoding::Utf32Le),
+            (utf32(text, u32::to_be_bytes), Encoding::Utf32Be),
+        ];
+        for (bytes, encoding) in cases {
+            let bytes: Vec<u8> = encoding.bom().iter().copied().chain(bytes).collect();
+            let mut s = Chars::new(bytes.into_iter());
+            assert_eq!(Some(&'{'), s.peek());
+            assert_eq!(encoding.bom().len(), s.current_position().offset);
+            let r: String = s.by_ref().collect();
+            assert_eq!(text, r);
+            assert_eq!(Some(encoding), s.encoding());
+            assert!(s.had_bom());
+        }
+    }
+
+    #[test]
+    fn utf8_bom_policy() {
+        let bytes = || vec![0xEF, 0xBB, 0xBF, b'1'].into_iter();
+        let mut s = Chars::new(bytes());
+        assert_eq!(None, s.next());
+        assert_eq!(Some(Err(TokenizeError::ByteOrderMark)), s.status);
+        let mut s = Chars::new(bytes()).with_bom_policy(BomPolicy::Skip);
+        assert_eq!("1", s.by_ref().collect::<String>());
+        assert!(!s.had_bom());
+        let mut s = Chars::new(bytes()).with_bom_policy(BomPolicy::Report);
+        assert_eq!("1", s.by_ref().collect::<String>());
+        assert!(s.had_bom());
+    }
+
+    #[test]
+    fn forced_encoding() {
+        let bytes = utf16("12", u16::to_le_bytes);
+        let s = Chars::new(bytes.into_iter()).with_encoding(Encoding::Utf8);
+        assert_eq!("1\u{0}2\u{0}", s.collect::<String>());
+        let bytes: Vec<u8> = vec![0xFF, 0xFE, b'1', 0];
+        let mut s = Chars::new(bytes.into_iter()).with_encoding(Encoding::Utf16Le);
+        assert_eq!("1", s.by_ref().collect::<String>());
+        assert!(s.had_bom());
+    }
 
-        if s.ne(t) {
-            for (cs, ct) in s2.zip(t2) {
-                eprintln!("{} {}", cs, ct);
+    #[test]
+    fn invalid_utf16_and_utf32() {
+        // A lone low surrogate
+        let mut s = Chars::new(vec![b'1', 0, 0x00, 0xDC].into_iter());
+        assert_eq!("1", s.by_ref().collect::<String>());
+        assert_eq!(Some(Err(TokenizeError::InvalidUTF16)), s.status);
+        // A high surrogate followed by something else than a low surrogate
+        let mut s = Chars::new(vec![b'1', 0, 0x00, 0xD8, b'1', 0].into_iter());
+        assert_eq!("1", s.by_ref().collect::<String>());
+        assert_eq!(Some(Err(TokenizeError::InvalidUTF16)), s.status);
+        // An odd number of bytes
+        let mut s = Chars::new(vec![b'1', 0, b'1'].into_iter());
+        assert_eq!("1", s.by_ref().collect::<String>());
+        assert_eq!(Some(Err(TokenizeError::InvalidUTF16)), s.status);
+        // Out of the Unicode range
+        let mut s = Chars::new(vec![b'1', 0, 0, 0, 0, 0, 0x11, 0].into_iter());
+        assert_eq!("1", s.by_ref().collect::<String>());
+        assert_eq!(Some(Err(TokenizeError::InvalidUTF32)), s.status);
+    }
+
+    /// Checks that `bytes` are decoded like `std::str::from_utf8` does, and that an error
+    /// is reported at the same place.
+    fn check_against_std(bytes: &[u8]) {
+        let mut s = Chars::new(Vec::from(bytes).into_iter())
+            .with_encoding(Encoding::Utf8)
+            .with_bom_policy(BomPolicy::Report);
+        let decoded: String = s.by_ref().collect();
+        let bom = if s.had_bom() { 3 } else { 0 };
+        match std::str::from_utf8(&bytes[bom..]) {
+            Ok(expected) => {
+                assert_eq!(expected, decoded, "{:X?}", bytes);
+                assert_eq!(Some(Ok(())), s.status, "{:X?}", bytes);
+            }
+            Err(e) => {
+                assert_eq!(&bytes[bom..bom + e.valid_up_to()], decoded.as_bytes(), "{:X?}", bytes);
+                assert_eq!(bom + e.valid_up_to(), s.current_position().offset, "{:X?}", bytes);
+                match &s.status {
+                    Some(Err(TokenizeError::InvalidUTF8(k))) => {
+                        assert_eq!(e.error_len().is_none(), *k == Utf8Error::Truncated, "{:X?}", bytes)
+                    }
+                    status => unreachable!("{:X?} {:?}", bytes, status),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn utf8_like_std_two_bytes() {
+        for a in 0..=255u8 {
+            check_against_std(&[a]);
+            for b in 0..=255u8 {
+                check_against_std(&[a, b]);
+            }
+        }
+    }
+
+    #[test]
+    fn utf8_like_std_boundaries() {
+        // The bounds of the ranges of table 3-7
+        let bounds = [
+            0x00, 0x7F, 0x80, 0x8F, 0x90, 0x9F, 0xA0, 0xBF, 0xC0, 0xC1, 0xC2, 0xDF, 0xE0, 0xE1,
+            0xEC, 0xED, 0xEE, 0xEF, 0xF0, 0xF1, 0xF3, 0xF4, 0xF5, 0xFF,
+        ];
+        for &a in &bounds {
+            for &b in &bounds {
+                for &c in &bounds {
+                    check_against_std(&[a, b, c]);
+                    for &d in &bounds {
+                        check_against_std(&[a, b, c, d]);
+                        check_against_std(&[b'x', a, b, c, d, b'x']);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn utf8_errors() {
+        let cases: Vec<(&[u8], Utf8Error)> = vec![
+            (&[0xC0, 0x80], Utf8Error::Overlong),
+            (&[0xC1, 0xBF], Utf8Error::Overlong),
+            (&[0xE0, 0x80, 0xAF], Utf8Error::Overlong),
+            (&[0xF0, 0x8F, 0xBF, 0xBF], Utf8Error::Overlong),
+            (&[0xED, 0xA0, 0x80], Utf8Error::Surrogate),
+            (&[0xF4, 0x90, 0x80, 0x80], Utf8Error::OutOfRange),
+            (&[0xF5, 0x80, 0x80, 0x80], Utf8Error::OutOfRange),
+            (&[0xFF], Utf8Error::OutOfRange),
+            (&[0x80], Utf8Error::UnexpectedContinuationByte),
+            (&[0xE2, 0x82, b'a'], Utf8Error::MissingContinuationByte),
+            (&[0xC3, 0xC3, 0xA9], Utf8Error::MissingContinuationByte),
+            (&[0xE2, 0x82], Utf8Error::Truncated),
+            (&[0xF0, 0x9D, 0x84], Utf8Error::Truncated),
+        ];
+        for (bytes, error) in cases {
+            let mut s = Chars::new(Vec::from(bytes).into_iter()).with_encoding(Encoding::Utf8);
+            assert_eq!(None, s.next());
+            assert_eq!(Some(Err(TokenizeError::InvalidUTF8(error))), s.status, "{:X?}", bytes);
+        }
+    }
+
+    /// Checks that `bytes` are decoded like `String::from_utf8_lossy` does.
+    fn check_lossy_against_std(bytes: &[u8]) {
+        let mut s = Chars::new(Vec::from(bytes).into_iter())
+            .with_encoding(Encoding::Utf8)
+            .with_bom_policy(BomPolicy::Skip)
+            .lossy();
+        let decoded: String = s.by_ref().collect();
+        let bom = if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) { 3 } else { 0 };
+        assert_eq!(String::from_utf8_lossy(&bytes[bom..]), decoded, "{:X?}", bytes);
+        assert_eq!(Some(Ok(())), s.status, "{:X?}", bytes);
+        assert_eq!(bytes.len(), s.current_position().offset, "{:X?}", bytes);
+        let invalid = bytes[bom..].utf8_chunks().filter(|c| !c.invalid().is_empty()).count();
+        assert_eq!(invalid, s.substitutions().len(), "{:X?}", bytes);
+    }
+
+    #[test]
+    fn lossy_like_std() {
+        let bounds = [
+            0x00, 0x7F, 0x80, 0x8F, 0x90, 0x9F, 0xA0, 0xBF, 0xC0, 0xC1, 0xC2, 0xDF, 0xE0, 0xE1,
+            0xEC, 0xED, 0xEE, 0xEF, 0xF0, 0xF1, 0xF3, 0xF4, 0xF5, 0xFF,
+        ];
+        for &a in &bounds {
+            for &b in &bounds {
+                for &c in &bounds {
+                    for &d in &bounds {
+                        check_lossy_against_std(&[a, b, c, d]);
+                        check_lossy_against_std(&[b'x', a, b, c, d, b'x']);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lossy_substitutions() {
+        // "caf\xE9" in Latin-1, an overlong "/" of three bytes, and a truncated "€" on the next line
+        let bytes = b"\"caf\xE9 \xE0\x80\xAF\"\n\"\xE2\x82".to_vec();
+        let mut s = Chars::new(bytes.into_iter()).lossy();
+        let r: String = s.by_ref().collect();
+        assert_eq!("\"caf\u{FFFD} \u{FFFD}\u{FFFD}\u{FFFD}\"\n\"\u{FFFD}", r);
+        assert_eq!(Some(Ok(())), s.status);
+        let found: Vec<(usize, usize, usize)> = s
+            .substitutions()
+            .iter()
+            .map(|p| (p.offset, p.line, p.column))
+            .collect();
+        assert_eq!(vec![(4, 1, 5), (6, 1, 7), (7, 1, 8), (8, 1, 9), (12, 2, 2)], found);
+    }
+
+    #[test]
+    fn lossy_utf16() {
+        // A lone high surrogate before "1", a lone low surrogate, and an odd byte
+        let bytes = vec![0x00, 0xD8, b'1', 0x00, 0x00, 0xDC, b'2', 0x00, b'3'];
+        let mut s = Chars::new(bytes.into_iter())
+            .with_encoding(Encoding::Utf16Le)
+            .lossy();
+        let r: String = s.by_ref().collect();
+        assert_eq!("\u{FFFD}1\u{FFFD}2\u{FFFD}", r);
+        assert_eq!(3, s.substitutions().len());
+        assert_eq!(9, s.current_position().offset);
+    }
+
+    #[test]
+    fn lossy_lexing() {
+        use crate::{lexer_iter::LexerIter, token::Token};
+        let bytes = b"[\"caf\xE9\"]".to_vec();
+        let mut l = LexerIter::new(Chars::new(bytes.into_iter()).lossy());
+        assert_eq!(Some(Ok(Token::ArrayBegin)), l.next());
+        assert_eq!(Some(Ok(Token::String("caf\u{FFFD}".into()))), l.next());
+        assert_eq!(Some(Ok(Token::ArrayEnd)), l.next());
+        assert_eq!(None, l.next());
+        assert_eq!(1, l.input().substitutions().len());
+    }
+
+    /// A reader giving one byte at a time, being interrupted in between, and failing at the end.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        interrupted: bool,
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupted = !self.interrupted;
+            if self.interrupted {
+                Err(io::Error::new(io::ErrorKind::Interrupted, "try again"))
+            } else if self.pos < self.data.len() {
+                buf[0] = self.data[self.pos];
+                self.pos += 1;
+                Ok(1)
+            } else {
+                Err(io::Error::new(io::ErrorKind::TimedOut, "stale file handle"))
+            }
+        }
+    }
+
+    #[test]
+    fn from_reader() {
+        let s = Chars::from_reader("a𝄞é".as_bytes());
+        assert_eq!("a𝄞é", s.collect::<String>());
+        let mut s = Chars::from_reader(Trickle {
+            data: "a𝄞é".as_bytes().to_vec(),
+            pos: 0,
+            interrupted: false,
+        });
+        assert_eq!("a𝄞é", s.by_ref().collect::<String>());
+        match s.status {
+            Some(Err(TokenizeError::Io(e))) => {
+                assert_eq!(io::ErrorKind::TimedOut, e.kind());
+                assert_eq!("stale file handle", e.to_string());
             }
-            panic!()
+            _ => unreachable!(),
         }
     }
 
@@ -331,7 +1096,7 @@ Box drawing alignment tests:                                          █
   ║└─╥─┘║  │╚═╤═╝│  │╘═╪═╛│  │╙─╀─╜│  ┃└─╂─┘┃  ░░▒▒▓▓██ ┊  ┆ ╎ ╏  ┇ ┋ ▏
   ╚══╩══╝  └──┴──┘  ╰──┴──╯  ╰──┴──╯  ┗━━┻━━┛           └╌╌┘ ╎ ┗╍╍┛ ┋  ▁▂▃▄▅▆▇█"#
             .to_string();
-        let s: Chars = o.clone().into();
+        let s: Chars<_> = o.clone().into();
         let s: String = s.collect();
         assert_eq!(s, o);
     }
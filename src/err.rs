@@ -1,4 +1,4 @@
-use crate::token::Token;
+use crate::{chars::Position, token::Token};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 /// An common error type
@@ -24,13 +24,101 @@ pub enum TokenizeError {
     /// The result of reading a surrogate pair is invalid UTF8
     InvalidSurrogatePairs,
     /// An unexpected token occured
-    UnexpectedToken(Token),
+    UnexpectedToken(Token<'static>),
     /// The given input is too long and should already have ended
     InputTooLong,
     /// Occurs when there is a bug
     InternalError(ErrorLoc),
     /// A byte sequence was invalid UTF8
-    InvalidUTF8,
+    InvalidUTF8(Utf8Error),
+    /// A byte sequence was invalid UTF16
+    InvalidUTF16,
+    /// A byte sequence was invalid UTF32
+    InvalidUTF32,
+    /// The input began with a UTF8 byte order mark, which was rejected
+    ByteOrderMark,
+    /// The input couldn't be read
+    Io(IoError),
+    /// A string was longer than the limit of the lexer
+    StringTooLong,
+    /// The integer part of a number had more digits than the limit of the lexer
+    TooManyIntDigits,
+    /// The fractional part of a number had more digits than the limit of the lexer
+    TooManyFracDigits,
+    /// The exponent of a number had more digits than the limit of the lexer
+    TooManyExpDigits,
+    /// The input had more tokens than the limit of the lexer
+    TooManyTokens,
+}
+
+/// An error a recovering lexer went past (see [`crate::lexer::SourceLexer::recovering`]).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LexicalError {
+    /// The error
+    pub error: TokenizeError,
+    /// Where it occured
+    pub position: Position,
+}
+
+/// The ways a byte sequence can be ill-formed UTF-8, following table 3-7 of the Unicode Standard.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Utf8Error {
+    /// A continuation byte (`80..=BF`) was found where a sequence should begin
+    UnexpectedContinuationByte,
+    /// A sequence was interrupted by a byte which isn't a continuation byte
+    MissingContinuationByte,
+    /// The input ended in the middle of a sequence
+    Truncated,
+    /// A code point was encoded with more bytes than needed (like `C0 80` for U+0000)
+    Overlong,
+    /// A surrogate code point (`U+D800..=U+DFFF`) was encoded
+    Surrogate,
+    /// A code point above U+10FFFF was encoded
+    OutOfRange,
+}
+
+impl From<Utf8Error> for TokenizeError {
+    fn from(e: Utf8Error) -> Self {
+        TokenizeError::InvalidUTF8(e)
+    }
+}
+
+/// An I/O error which occured while reading the input.
+///
+/// Unlike `std::io::Error`, it can be cloned and compared.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct IoError {
+    kind: std::io::ErrorKind,
+    message: String,
+}
+
+impl IoError {
+    /// Returns the kind of the original error.
+    #[must_use]
+    pub fn kind(&self) -> std::io::ErrorKind {
+        self.kind
+    }
+}
+
+impl std::fmt::Display for IoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<std::io::Error> for IoError {
+    fn from(e: std::io::Error) -> Self {
+        Self {
+            kind: e.kind(),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<std::io::Error> for TokenizeError {
+    fn from(e: std::io::Error) -> Self {
+        TokenizeError::Io(e.into())
+    }
 }
 /// Represents the location in the source code where an error occured
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -0,0 +1,309 @@
+//! Correctly rounded conversion of decimal numbers to binary floating-point numbers.
+//!
+//! The small numbers are converted exactly with float operations, the others by dividing big
+//! integers.
+
+use std::{cmp::Ordering, convert::TryFrom};
+
+use crate::number::ConversionError;
+
+/// The most significant digits a conversion reads: the numbers halfway between two floats have
+/// less, so the next ones only tell that the number is above the ones read.
+const MAX_DIGITS: usize = 800;
+
+/// A binary floating-point type.
+pub(crate) trait Float: Copy + std::ops::Neg<Output = Self> {
+    /// The number of bits of the significand, the implicit one included
+    const BITS: u32;
+    /// The exponent of the smallest normal numbers
+    const MIN_EXP: i128;
+    /// The exponent of the largest numbers
+    const MAX_EXP: i128;
+    /// The numbers of at least `10^(MAX_DECIMAL_EXP + 1)` overflow
+    const MAX_DECIMAL_EXP: i128;
+    /// The numbers below `10^MIN_DECIMAL_EXP` round to zero
+    const MIN_DECIMAL_EXP: i128;
+    const ZERO: Self;
+    const INFINITY: Self;
+
+    /// Returns `d * 10^k` when both are exactly represented, and so is the result once rounded.
+    fn fast_path(d: u64, k: i128) -> Option<Self>;
+
+    /// Returns `q * 2^e`, which must be a positive finite float.
+    fn from_parts(q: u64, e: i128) -> Self;
+}
+
+/// Powers of 10 which are exact `f64`s.
+const POW10_F64: [f64; 23] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16, 1e17, 1e18, 1e19,
+    1e20, 1e21, 1e22,
+];
+
+/// Powers of 10 which are exact `f32`s.
+const POW10_F32: [f32; 11] = [1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10];
+
+impl Float for f64 {
+    const BITS: u32 = 53;
+    const MIN_EXP: i128 = -1022;
+    const MAX_EXP: i128 = 1023;
+    const MAX_DECIMAL_EXP: i128 = 309;
+    const MIN_DECIMAL_EXP: i128 = -330;
+    const ZERO: Self = 0.0;
+    const INFINITY: Self = f64::INFINITY;
+
+    fn fast_path(d: u64, k: i128) -> Option<Self> {
+        let p = *POW10_F64.get(usize::try_from(k.unsigned_abs()).ok()?)?;
+        if d > 1 << 53 {
+            return None;
+        }
+        Some(if k < 0 { d as f64 / p } else { d as f64 * p })
+    }
+
+    fn from_parts(q: u64, e: i128) -> Self {
+        let bits = if q >> 52 == 0 {
+            q
+        } else {
+            ((e + 52 + 1023) as u64) << 52 | (q & ((1 << 52) - 1))
+        };
+        f64::from_bits(bits)
+    }
+}
+
+impl Float for f32 {
+    const BITS: u32 = 24;
+    const MIN_EXP: i128 = -126;
+    const MAX_EXP: i128 = 127;
+    const MAX_DECIMAL_EXP: i128 = 39;
+    const MIN_DECIMAL_EXP: i128 = -50;
+    const ZERO: Self = 0.0;
+    const INFINITY: Self = f32::INFINITY;
+
+    fn fast_path(d: u64, k: i128) -> Option<Self> {
+        let p = *POW10_F32.get(usize::try_from(k.unsigned_abs()).ok()?)?;
+        if d > 1 << 24 {
+            return None;
+        }
+        Some(if k < 0 { d as f32 / p } else { d as f32 * p })
+    }
+
+    fn from_parts(q: u64, e: i128) -> Self {
+        let bits = if q >> 23 == 0 {
+            q as u32
+        } else {
+            ((e + 23 + 127) as u32) << 23 | (q as u32 & ((1 << 23) - 1))
+        };
+        f32::from_bits(bits)
+    }
+}
+
+/// Returns the float nearest to `D * 10^k` (ties to even), `D` being the integer of the `count`
+/// `digits`, whose first and last ones aren't zero.
+///
+/// Fails with [`ConversionError::TooLarge`] when it is infinite, and with
+/// [`ConversionError::TooPrecise`] when it is zero.
+pub(crate) fn to_float<F: Float>(
+    digits: impl Iterator<Item = u8>,
+    count: usize,
+    mut k: i128,
+) -> Result<F, ConversionError> {
+    // D * 10^k is in [10^(count + k - 1), 10^(count + k))
+    if count as i128 + k - 1 > F::MAX_DECIMAL_EXP {
+        return Err(ConversionError::TooLarge);
+    }
+    if (count as i128) + k < F::MIN_DECIMAL_EXP {
+        return Err(ConversionError::TooPrecise);
+    }
+    let mut digits: Vec<u8> = digits.take(MAX_DIGITS).collect();
+    if count <= 19 {
+        let d = digits.iter().fold(0, |d, &digit| d * 10 + u64::from(digit));
+        if let Some(f) = F::fast_path(d, k) {
+            return Ok(f);
+        }
+    }
+    if count > MAX_DIGITS {
+        // The last digit isn't zero, so neither are the ones which aren't read
+        digits.push(1);
+        k += (count - MAX_DIGITS - 1) as i128;
+    }
+    let mut num = Big::from_digits(&digits);
+    let mut den = Big::from(1);
+    // `k` is bounded by the checks above
+    if k >= 0 {
+        num.mul_pow10(k as usize);
+    } else {
+        den.mul_pow10(k.unsigned_abs() as usize);
+    }
+    let (q, e) = round(&num, &den, F::BITS, F::MIN_EXP);
+    if q == 0 {
+        Err(ConversionError::TooPrecise)
+    } else if e + i128::from(F::BITS) - 1 > F::MAX_EXP {
+        Err(ConversionError::TooLarge)
+    } else {
+        Ok(F::from_parts(q, e))
+    }
+}
+
+/// Returns `q` and `e` such that `q * 2^e` is nearest to `num / den` (ties to even), `q` having
+/// `bits` bits unless the exponent of its first bit would be below `min_exp`.
+fn round(num: &Big, den: &Big, bits: u32, min_exp: i128) -> (u64, i128) {
+    let bits_i = i128::from(bits);
+    // num / den is in [2^(e + bits - 1), 2^(e + bits + 1))
+    let mut e = num.bit_len() as i128 - den.bit_len() as i128 - bits_i;
+    // Subnormal numbers have less bits
+    e = e.max(min_exp - bits_i + 1);
+    let (mut q, mut rest) = divide(num, den, e, bits);
+    if q >> bits != 0 {
+        // The last bit of the quotient is half the new divisor
+        rest = match (q & 1, rest) {
+            (0, _) => Ordering::Less,
+            (_, None) => Ordering::Equal,
+            _ => Ordering::Greater,
+        }
+        .into();
+        q >>= 1;
+        e += 1;
+    }
+    let rest = rest.unwrap_or(Ordering::Less);
+    if rest == Ordering::Greater || (rest == Ordering::Equal && q & 1 == 1) {
+        q += 1;
+        if q >> bits != 0 {
+            q >>= 1;
+            e += 1;
+        }
+    }
+    (q, e)
+}
+
+/// Returns the quotient of `num / (den * 2^e)`, which must be below `2^(bits + 1)`, and how twice
+/// the remainder compares to the divisor, unless the remainder is zero.
+fn divide(num: &Big, den: &Big, e: i128, bits: u32) -> (u64, Option<Ordering>) {
+    let mut num = num.clone();
+    let mut den = den.clone();
+    if e < 0 {
+        num.shl(e.unsigned_abs() as usize);
+    } else {
+        den.shl(e as usize);
+    }
+    let mut d = den.clone();
+    d.shl(bits as usize);
+    let mut q = 0;
+    for i in (0..=bits).rev() {
+        if num.cmp(&d) != Ordering::Less {
+            num.sub(&d);
+            q |= 1 << i;
+        }
+        d.shr1();
+    }
+    if num.0.is_empty() {
+        return (q, None);
+    }
+    num.shl(1);
+    (q, Some(num.cmp(&den)))
+}
+
+/// A natural number, with its 32-bit limbs least significant first and no leading zero limbs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Big(Vec<u32>);
+
+impl From<u32> for Big {
+    fn from(n: u32) -> Self {
+        let mut b = Big(vec![n]);
+        b.trim();
+        b
+    }
+}
+
+impl Big {
+    /// Returns the number of the decimal `digits`.
+    fn from_digits(digits: &[u8]) -> Self {
+        let mut b = Big(Vec::new());
+        for chunk in digits.chunks(9) {
+            let n = chunk.iter().fold(0, |n, &d| n * 10 + u32::from(d));
+            b.mul_add(10u32.pow(chunk.len() as u32), n);
+        }
+        b
+    }
+
+    fn trim(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    /// Multiplies by `m`, then adds `a`.
+    fn mul_add(&mut self, m: u32, a: u32) {
+        let mut carry = u64::from(a);
+        for limb in &mut self.0 {
+            let x = u64::from(*limb) * u64::from(m) + carry;
+            *limb = x as u32;
+            carry = x >> 32;
+        }
+        if carry > 0 {
+            self.0.push(carry as u32);
+        }
+        self.trim();
+    }
+
+    /// Multiplies by `10^k`.
+    fn mul_pow10(&mut self, mut k: usize) {
+        while k >= 9 {
+            self.mul_add(1_000_000_000, 0);
+            k -= 9;
+        }
+        self.mul_add(10u32.pow(k as u32), 0);
+    }
+
+    /// Multiplies by `2^n`.
+    fn shl(&mut self, n: usize) {
+        if self.0.is_empty() {
+            return;
+        }
+        let (limbs, bits) = (n / 32, n % 32);
+        if bits > 0 {
+            let mut carry = 0;
+            for limb in &mut self.0 {
+                let x = *limb;
+                *limb = x << bits | carry;
+                carry = x >> (32 - bits);
+            }
+            if carry > 0 {
+                self.0.push(carry);
+            }
+        }
+        self.0.splice(0..0, std::iter::repeat_n(0, limbs));
+    }
+
+    /// Divides by 2, rounding down.
+    fn shr1(&mut self) {
+        let mut carry = 0;
+        for limb in self.0.iter_mut().rev() {
+            let x = *limb;
+            *limb = x >> 1 | carry;
+            carry = x << 31;
+        }
+        self.trim();
+    }
+
+    /// Subtracts `other`, which must not be larger.
+    fn sub(&mut self, other: &Big) {
+        let mut borrow = 0;
+        for (i, limb) in self.0.iter_mut().enumerate() {
+            let y = i64::from(other.0.get(i).copied().unwrap_or_default()) + borrow;
+            let x = i64::from(*limb) - y;
+            borrow = if x < 0 { 1 } else { 0 };
+            *limb = (x + (borrow << 32)) as u32;
+        }
+        self.trim();
+    }
+
+    fn bit_len(&self) -> usize {
+        match self.0.last() {
+            Some(last) => self.0.len() * 32 - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn cmp(&self, other: &Big) -> Ordering {
+        self.0.len().cmp(&other.0.len()).then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
@@ -0,0 +1,28 @@
+//! Common tasks in parsing
+
+use crate::source::Source;
+
+use crate::err::TokenizeError;
+
+/// A `Reader` with one more argument. Used to parse a constant string.
+///
+/// The first char which doesn't match isn't consumed.
+pub(crate) fn read_string<'a, Src: Source<'a>>(
+    input: &mut Src,
+    s: &str,
+) -> ReaderResult<()> {
+    for sc in s.chars() {
+        match input.peek_char() {
+            Some(ic) => {
+                if ic != sc {
+                    return Err(TokenizeError::LitteralDidntMatch);
+                }
+                input.next_char();
+            }
+            None => return Err(TokenizeError::LitteralDidntMatch),
+        }
+    }
+    Ok(())
+}
+
+pub(crate) type ReaderResult<T> = Result<T, TokenizeError>;
@@ -0,0 +1,507 @@
+//! The lexing of the JSON5 extensions (<https://spec.json5.org>).
+
+use std::borrow::Cow;
+
+use crate::{
+    err::TokenizeError,
+    idioms::{self, ReaderResult},
+    lexer::{
+        is_line_terminator, read_digit, read_exp, read_hexdigit, read_more_digits, read_white_space_or_comment,
+        Limits, Options, SurrogateDecoder,
+    },
+    number::{Digit, Number, Sign},
+    source::Source,
+    token::{JsonString, NonFinite, Token},
+};
+
+/// Reads a token which is lexed differently in JSON5, or `None` if the next one is lexed as in
+/// JSON (the structural characters).
+pub(crate) fn read_token<'a, Src: Source<'a>>(input: &mut Src, options: &Options) -> ReaderResult<Option<Token<'a>>> {
+    let limits = &options.limits;
+    match input.peek_char() {
+        Some(q @ ('"' | '\'')) => {
+            input.next_char();
+            Ok(Some(Token::String(read_string(input, q, options)?)))
+        }
+        Some('0'..='9' | '-' | '+' | '.') => read_number(input, limits).map(Some),
+        Some(c) if is_white_space(c) || c == '/' => {
+            read_white_space_or_comment(input, options, is_white_space).map(Some)
+        }
+        Some(c) if is_identifier_start(c) || c == '\\' => read_identifier(input, limits.string_length).map(Some),
+        Some('[' | ']' | '{' | '}' | ':' | ',') | None => Ok(None),
+        Some(_) => Err(TokenizeError::UnkownToken),
+    }
+}
+
+/// Whether `c` is JSON5 white space (the Unicode space separators included).
+fn is_white_space(c: char) -> bool {
+    matches!(
+        c,
+        ' ' | '\t'
+            | '\n'
+            | '\r'
+            | '\u{000B}'
+            | '\u{000C}'
+            | '\u{00A0}'
+            | '\u{1680}'
+            | '\u{2000}'..='\u{200A}'
+            | '\u{2028}'
+            | '\u{2029}'
+            | '\u{202F}'
+            | '\u{205F}'
+            | '\u{3000}'
+            | '\u{FEFF}'
+    )
+}
+
+/// Whether an identifier can begin with `c`.
+///
+/// The Unicode letters are approximated by the alphabetic chars.
+fn is_identifier_start(c: char) -> bool {
+    c == '$' || c == '_' || c.is_alphabetic()
+}
+
+/// Whether an identifier can go on with `c`.
+fn is_identifier_part(c: char) -> bool {
+    is_identifier_start(c) || c.is_numeric() || c == '\u{200C}' || c == '\u{200D}'
+}
+
+/// Reads a JSON5 string, after its opening `quote`, failing once it is longer than the limit
+/// of `options`.
+fn read_string<'a, Src: Source<'a>>(input: &mut Src, quote: char, options: &Options) -> ReaderResult<JsonString<'a>> {
+    let limit = options.limits.string_length;
+    let mut a = String::new();
+    // The runs of the source only stop at the double quotes
+    let runs = quote == '"';
+    if runs {
+        if let Some(run) = input.borrow_string_chars(limit) {
+            if input.peek_char() == Some('"') {
+                input.next_char();
+                return Ok(JsonString::Text(Cow::Borrowed(run)));
+            }
+            a.push_str(run);
+        }
+    }
+    let mut decoder = SurrogateDecoder::new(options.surrogates);
+    loop {
+        if decoder.len(&a) > limit {
+            return Err(TokenizeError::StringTooLong);
+        }
+        if input.peek_char() != Some('\\') {
+            decoder.flush(&mut a);
+        }
+        if runs {
+            input.read_string_chars(&mut a, limit);
+        }
+        match input.peek_char() {
+            Some(c) if c == quote => {
+                input.next_char();
+                return Ok(decoder.finish(a));
+            }
+            Some('\\') => {
+                input.next_char();
+                if let Some(c) = read_escape_sequence(input, &mut decoder, &mut a)? {
+                    a.push(c);
+                }
+            }
+            Some('\n' | '\r') => return Err(TokenizeError::ControlCharacterUnescaped),
+            Some(c) => {
+                input.next_char();
+                a.push(c);
+            }
+            None => return Err(TokenizeError::InputEndedEarly),
+        }
+    }
+}
+
+/// Reads a JSON5 escape sequence of the string `a`, after its `\`. A line continuation or a
+/// surrogate gives `None`.
+fn read_escape_sequence<'a, Src: Source<'a>>(
+    input: &mut Src,
+    decoder: &mut SurrogateDecoder,
+    a: &mut String,
+) -> ReaderResult<Option<char>> {
+    let c = input.peek_char();
+    if c != Some('u') && !c.is_some_and(is_line_terminator) {
+        decoder.flush(a);
+    }
+    match c {
+        Some('u') => decoder.read_escape_sequence(input, a),
+        Some('x') => {
+            input.next_char();
+            let h = read_hexdigit(input)? << 4 | read_hexdigit(input)?;
+            Ok(Some(char::from(h)))
+        }
+        Some('0') => {
+            input.next_char();
+            match input.peek_char() {
+                Some('0'..='9') => Err(TokenizeError::UnkownEscapeSequence),
+                _ => Ok(Some('\0')),
+            }
+        }
+        Some('1'..='9') => Err(TokenizeError::UnkownEscapeSequence),
+        Some('\r') => {
+            input.next_char();
+            if input.peek_char() == Some('\n') {
+                input.next_char();
+            }
+            Ok(None)
+        }
+        Some(c) if is_line_terminator(c) => {
+            input.next_char();
+            Ok(None)
+        }
+        Some(c) => {
+            input.next_char();
+            Ok(Some(match c {
+                'b' => '\u{0008}',
+                'f' => '\u{000C}',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'v' => '\u{000B}',
+                // Any other char stands for itself
+                c => c,
+            }))
+        }
+        None => Err(TokenizeError::InputEndedEarly),
+    }
+}
+
+/// Reads a JSON5 number: a JSON one which may begin with `+` or a decimal point and end with a
+/// decimal point, an hexadecimal integer, `Infinity` or `NaN`.
+fn read_number<'a, Src: Source<'a>>(input: &mut Src, limits: &Limits) -> ReaderResult<Token<'a>> {
+    let sign = match input.peek_char() {
+        Some('+') => {
+            input.next_char();
+            Sign::Positive
+        }
+        Some('-') => {
+            input.next_char();
+            Sign::Negative
+        }
+        _ => Sign::Positive,
+    };
+    let int = match input.peek_char() {
+        Some('I') => {
+            idioms::read_string(input, "Infinity")?;
+            return Ok(Token::NonFinite(match sign {
+                Sign::Positive => NonFinite::Infinity,
+                Sign::Negative => NonFinite::NegInfinity,
+            }));
+        }
+        Some('N') => {
+            idioms::read_string(input, "NaN")?;
+            return Ok(Token::NonFinite(NonFinite::NaN));
+        }
+        Some('0') => {
+            input.next_char();
+            if let Some('x' | 'X') = input.peek_char() {
+                input.next_char();
+                let hex = read_hex_digits(input, limits.int_digits)?;
+                return Ok(Token::Number(Number::from_hex_digits(sign, &hex)));
+            }
+            if limits.int_digits == 0 {
+                return Err(TokenizeError::TooManyIntDigits);
+            }
+            vec![Digit::D0]
+        }
+        Some('.') => {
+            input.next_char();
+            let first = read_digit(input)?;
+            let frac = read_more_digits(input, vec![first], limits.frac_digits, TokenizeError::TooManyFracDigits)?;
+            let exp = read_exp(input, limits)?;
+            return Ok(Token::Number(Number::new(sign, vec![Digit::D0], Some(frac), exp)));
+        }
+        _ => {
+            let first = read_digit(input)?;
+            read_more_digits(input, vec![first], limits.int_digits, TokenizeError::TooManyIntDigits)?
+        }
+    };
+    let frac = match input.peek_char() {
+        Some('.') => {
+            input.next_char();
+            Some(read_more_digits(input, Vec::new(), limits.frac_digits, TokenizeError::TooManyFracDigits)?)
+                .filter(|f| !f.is_empty())
+        }
+        _ => None,
+    };
+    let exp = read_exp(input, limits)?;
+    Ok(Token::Number(Number::new(sign, int, frac, exp)))
+}
+
+/// Reads one or more hexadecimal digits, returning their values, failing beyond `limit` digits.
+fn read_hex_digits<'a, Src: Source<'a>>(input: &mut Src, limit: usize) -> ReaderResult<Vec<u8>> {
+    let mut r = Vec::new();
+    while let Some(d) = input.peek_char().and_then(|c| c.to_digit(16)) {
+        if r.len() == limit {
+            return Err(TokenizeError::TooManyIntDigits);
+        }
+        input.next_char();
+        r.push(d as u8);
+    }
+    match input.peek_char() {
+        _ if !r.is_empty() => Ok(r),
+        Some(_) => Err(TokenizeError::ExpectedAHexdigit),
+        None => Err(TokenizeError::InputEndedEarly),
+    }
+}
+
+/// Reads an identifier: a key, a literal, or an unsigned `Infinity` or `NaN`, failing once it
+/// is longer than `limit` bytes.
+fn read_identifier<'a, Src: Source<'a>>(input: &mut Src, limit: usize) -> ReaderResult<Token<'a>> {
+    let start = input.current_position().offset;
+    let mut name = String::new();
+    let mut escaped = false;
+    loop {
+        let c = match input.peek_char() {
+            Some('\\') => {
+                input.next_char();
+                if input.next_char() != Some('u') {
+                    return Err(TokenizeError::UnkownEscapeSequence);
+                }
+                let mut code = 0;
+                for _ in 0..4 {
+                    code = code << 4 | u32::from(read_hexdigit(input)?);
+                }
+                escaped = true;
+                match std::char::from_u32(code) {
+                    Some(c) if is_identifier_part(c) => c,
+                    Some(_) => return Err(TokenizeError::UnkownToken),
+                    None => return Err(TokenizeError::InvalidUnicodeCodePoint),
+                }
+            }
+            Some(c) if is_identifier_part(c) => {
+                input.next_char();
+                c
+            }
+            _ => break,
+        };
+        if name.is_empty() && !is_identifier_start(c) {
+            return Err(TokenizeError::UnkownToken);
+        }
+        name.push(c);
+        if name.len() > limit {
+            return Err(TokenizeError::StringTooLong);
+        }
+    }
+    if escaped {
+        return Ok(Token::Identifier(Cow::Owned(name)));
+    }
+    Ok(match name.as_str() {
+        "true" => Token::True,
+        "false" => Token::False,
+        "null" => Token::Null,
+        _ => match input.text(start, input.current_position().offset) {
+            Some(text) => Token::Identifier(Cow::Borrowed(text)),
+            None => Token::Identifier(Cow::Owned(name)),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::parse_and_valid,
+        automaton::{Action, Automaton},
+        chars::Chars,
+        lexer::{Dialect, Limits, Surrogates},
+        lexer_bytes::LexerBytes,
+        lexer_iter::LexerIter,
+        value::Value,
+    };
+
+    /// Lexes `s` as JSON5, without the white spaces.
+    fn tokens(s: &str) -> Result<Vec<Token<'static>>, TokenizeError> {
+        let tokens: Result<Vec<Token>, _> = LexerIter::new(Chars::from(s)).with_dialect(Dialect::Json5).collect();
+        let mut tokens = tokens?;
+        tokens.retain(|t| *t != Token::WhiteSpace);
+        Ok(tokens)
+    }
+
+    fn number(s: &str) -> Token<'static> {
+        match tokens(s) {
+            Ok(mut t) if t.len() == 1 => t.remove(0),
+            r => unreachable!("{}: {:?}", s, r),
+        }
+    }
+
+    fn string(s: &str) -> Result<Token<'static>, TokenizeError> {
+        tokens(s).map(|mut t| t.remove(0))
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(Ok(Token::String("a\"b".into())), string("'a\"b'"));
+        assert_eq!(Ok(Token::String("a'b".into())), string(r#""a'b""#));
+        assert_eq!(Ok(Token::String("it's".into())), string(r"'it\'s'"));
+        assert_eq!(Ok(Token::String("ab".into())), string("'a\\\nb'"));
+        assert_eq!(Ok(Token::String("ab".into())), string("'a\\\r\nb'"));
+        assert_eq!(Ok(Token::String("ab".into())), string("\"a\\\u{2028}b\""));
+        assert_eq!(Ok(Token::String("\u{B}\0A\u{E9}q\t".into())), string(r"'\v\0\x41\xe9\q	'"));
+        assert_eq!(Ok(Token::String("\u{1D11E}".into())), string(r"'\uD834\uDD1E'"));
+        assert_eq!(Err(TokenizeError::ControlCharacterUnescaped), string("'a\nb'"));
+        assert_eq!(Err(TokenizeError::UnkownEscapeSequence), string(r"'\1'"));
+        assert_eq!(Err(TokenizeError::UnkownEscapeSequence), string(r"'\01'"));
+        assert_eq!(Err(TokenizeError::InputEndedEarly), string("'abc"));
+    }
+
+    #[test]
+    fn surrogates() {
+        let string = |s, policy| LexerIter::new(Chars::from(s)).with_dialect(Dialect::Json5).with_surrogates(policy).next();
+        assert_eq!(
+            Some(Ok(Token::String(JsonString::Utf16(vec![0xDC00, 0x0A, 0xD834, 0x41])))),
+            string(r"'\uDC00\n\uD834\x41'", Surrogates::Utf16)
+        );
+        assert_eq!(
+            Some(Ok(Token::String(JsonString::Text("\u{1D11E}".into())))),
+            string("'\\uD834\\\n\\uDD1E'", Surrogates::Wtf8)
+        );
+        assert_eq!(
+            Some(Ok(Token::String(JsonString::Replaced("\u{FFFD}".into())))),
+            string(r"'\uDC00'", Surrogates::Replace)
+        );
+        assert_eq!(Some(Err(TokenizeError::InvalidUnicodeCodePoint)), string(r"'\uDC00'", Surrogates::Reject));
+    }
+
+    #[test]
+    fn numbers() {
+        let n = |s: &str| match LexerIter::new(Chars::from(s)).next() {
+            Some(Ok(Token::Number(n))) => n,
+            t => unreachable!("{}: {:?}", s, t),
+        };
+        assert_eq!(number(".5"), Token::Number(n("0.5")));
+        assert_eq!(number("5."), Token::Number(n("5")));
+        assert_eq!(number("5.e2"), Token::Number(n("5e2")));
+        assert_eq!(number("+1.5"), Token::Number(n("1.5")));
+        assert_eq!(number("-.5E-1"), Token::Number(n("-0.5E-1")));
+        assert_eq!(number("0x0"), Token::Number(n("0")));
+        assert_eq!(number("0xFF"), Token::Number(n("255")));
+        assert_eq!(number("-0XdecaFBad"), Token::Number(n("-3737844653")));
+        assert_eq!(number("0x10000000000000000"), Token::Number(n("18446744073709551616")));
+        assert_eq!(number("Infinity"), Token::Identifier("Infinity".into()));
+        assert_eq!(number("+Infinity"), Token::NonFinite(NonFinite::Infinity));
+        assert_eq!(number("-Infinity"), Token::NonFinite(NonFinite::NegInfinity));
+        assert_eq!(number("-NaN"), Token::NonFinite(NonFinite::NaN));
+        assert_eq!(Err(TokenizeError::InputEndedEarly), tokens("."));
+        assert_eq!(Err(TokenizeError::ExpectedADigit), tokens(".e1"));
+        assert_eq!(Err(TokenizeError::ExpectedADigit), tokens("+x"));
+        assert_eq!(Err(TokenizeError::InputEndedEarly), tokens("0x"));
+        assert_eq!(Err(TokenizeError::LitteralDidntMatch), tokens("-Inf"));
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(
+            Ok(vec![
+                Token::Identifier("$a_1".into()),
+                Token::Identifier("été".into()),
+                Token::Identifier("ab".into()),
+                Token::True,
+                Token::Identifier("true".into()),
+            ]),
+            tokens(r"$a_1 été a\u0062 true \u0074rue")
+        );
+        assert_eq!(Err(TokenizeError::UnkownToken), tokens(r"\u0031a"));
+        assert_eq!(Err(TokenizeError::UnkownEscapeSequence), tokens(r"a\x41"));
+        let mut l = LexerBytes::from("abc").with_dialect(Dialect::Json5);
+        assert!(matches!(l.next(), Some(Ok(Token::Identifier(Cow::Borrowed("abc"))))));
+    }
+
+    #[test]
+    fn white_space_and_comments() {
+        assert_eq!(
+            Ok(vec![Token::Number(Number::new(Sign::Positive, vec![Digit::D1], None, None))]),
+            tokens(" \u{FEFF}\u{A0}\u{B}\u{C}\u{2003}// one\n/* a * / comment **/1//")
+        );
+        assert_eq!(Err(TokenizeError::InputEndedEarly), tokens("/* a"));
+        assert_eq!(Err(TokenizeError::UnkownToken), tokens("/ a"));
+    }
+
+    #[test]
+    fn limits() {
+        let limits = Limits {
+            string_length: 3,
+            int_digits: 2,
+            frac_digits: 1,
+            exp_digits: 1,
+            tokens: usize::MAX,
+        };
+        let last = |s| LexerIter::new(Chars::from(s)).with_dialect(Dialect::Json5).with_limits(limits).last();
+        assert_eq!(Some(Ok(Token::ArrayEnd)), last("[abc, 'abc', 0xff, 12., .5e1]"));
+        assert_eq!(Some(Err(TokenizeError::StringTooLong)), last("['abcd']"));
+        assert_eq!(Some(Err(TokenizeError::StringTooLong)), last("{abcd: 1}"));
+        assert_eq!(Some(Err(TokenizeError::TooManyIntDigits)), last("[0xfff]"));
+        assert_eq!(Some(Err(TokenizeError::TooManyIntDigits)), last("[+123]"));
+        assert_eq!(Some(Err(TokenizeError::TooManyFracDigits)), last("[.12]"));
+        assert_eq!(Some(Err(TokenizeError::TooManyFracDigits)), last("[1.12]"));
+        assert_eq!(Some(Err(TokenizeError::TooManyExpDigits)), last("[1e12]"));
+    }
+
+    #[test]
+    fn documents() {
+        let document = r"// A JSON5 document
+{
+  unquoted: 'and you can quote me on that',
+  singleQuotes: 'I can use &quot;double quotes&quot; here',
+  lineBreaks: 'Look, Mom! \
+No \\n\'s!',
+  hexadecimal: 0xdecaf,
+  leadingDecimalPoint: .8675309, andTrailing: 8675309.,
+  positiveSign: +1,
+  trailingComma: 'in objects', andIn: ['arrays',],
+  'backwardsCompatible': 'with JSON',
+  null: [Infinity, -Infinity, NaN, true],
+}
+";
+        let value = parse_and_valid(LexerBytes::from(document).with_dialect(Dialect::Json5));
+        match value {
+            Ok(Value::Object(o)) => {
+                let keys: Vec<&str> = o.iter().filter_map(|(k, _)| k.as_str()).collect();
+                assert_eq!(
+                    vec![
+                        "unquoted",
+                        "singleQuotes",
+                        "lineBreaks",
+                        "hexadecimal",
+                        "leadingDecimalPoint",
+                        "andTrailing",
+                        "positiveSign",
+                        "trailingComma",
+                        "andIn",
+                        "backwardsCompatible",
+                        "null",
+                    ],
+                    keys
+                );
+                assert_eq!(Value::String("Look, Mom! No \\n's!".into()), o[2].1);
+                assert_eq!(
+                    Value::Array(vec![
+                        Value::NonFinite(NonFinite::Infinity),
+                        Value::NonFinite(NonFinite::NegInfinity),
+                        Value::NonFinite(NonFinite::NaN),
+                        Value::True,
+                    ]),
+                    o[10].1
+                );
+            }
+            v => unreachable!("{:?}", v),
+        }
+        assert!(parse_and_valid(LexerBytes::from("[1,]").with_dialect(Dialect::Json5)).is_ok());
+        for invalid in ["[1,,]", "[,]", "{,}", "{a}", "{-Infinity: 1}", "[foo]", "{a: 1,,}"] {
+            let r = parse_and_valid(LexerBytes::from(invalid).with_dialect(Dialect::Json5));
+            assert!(matches!(r, Err(TokenizeError::UnexpectedToken(_))), "{}: {:?}", invalid, r);
+        }
+    }
+
+    #[test]
+    fn strict_json_is_the_default() {
+        for json5 in ["[1,]", "{a: 1}", "['a']", "[.5]", "[+1]", "[0x1]", "[Infinity]", "// c\n1"] {
+            let last = Automaton::new(LexerBytes::from(json5)).last();
+            assert!(matches!(last, Some(Err(_))), "{}: {:?}", json5, last);
+            let last = Automaton::new(LexerBytes::from(json5).with_dialect(Dialect::Json5)).last();
+            assert_eq!(Some(Ok(Action::TheEnd)), last, "{}", json5);
+        }
+    }
+}
@@ -1,5 +1,1980 @@
-use crate::{err::TokenizeError, token::Token};
+//! The JSON lexer, reading any [`Source`].
 
-pub trait Lexer: Iterator<Item = Token> {
-    fn report(&self) -> &Option<Result<(),TokenizeError>>;
-}
\ No newline at end of file
+use crate::{chars::Position, err::{internal_error, LexicalError, TokenizeError}, json5, idioms::{self, ReaderResult}, number::{Digit, Number, Sign}, source::{Recorder, Source}, token::{JsonString, LosslessToken, Span, Token}};
+use std::convert::TryFrom;
+use std::{borrow::Cow, marker::PhantomData, ops::ShlAssign};
+
+/// A lexer, yielding tokens whose strings may borrow from the input for `'a`.
+///
+/// The iterator ends (`None`) at the end of the input, or right after yielding the first
+/// error.
+pub trait Lexer<'a>: Iterator<Item = Result<Token<'a>, TokenizeError>> {
+    /// Returns the position of the next char to be read (or where the error occured).
+    fn current_position(&self) -> Position;
+
+    /// Returns the dialect of the input.
+    fn dialect(&self) -> Dialect {
+        Dialect::Json
+    }
+
+    /// Pairs each token with its [`Span`].
+    fn spanned(self) -> Spanned<Self>
+    where
+        Self: Sized,
+    {
+        Spanned { lexer: self }
+    }
+}
+
+impl<'a, L: Lexer<'a> + ?Sized> Lexer<'a> for Box<L> {
+    fn current_position(&self) -> Position {
+        (**self).current_position()
+    }
+
+    fn dialect(&self) -> Dialect {
+        (**self).dialect()
+    }
+}
+
+/// The flavours of JSON a lexer can read.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Dialect {
+    /// RFC 8259 JSON
+    #[default]
+    Json,
+    /// JSON with comments and trailing commas, as in the settings of many editors
+    Jsonc,
+    /// JSON5 (<https://spec.json5.org>)
+    Json5,
+}
+
+/// What the lexer does with the lone surrogate escapes of the strings (like `"\uDC00"`, or
+/// `"\uD834"` not followed by the escape of a low surrogate), which don't stand for any char.
+///
+/// The strings decoded with another policy than [`Surrogates::Reject`] are the
+/// [`JsonString`] variants named after it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Surrogates {
+    /// Fail on them
+    #[default]
+    Reject,
+    /// Replace each of them by U+FFFD
+    Replace,
+    /// Keep them, as WTF-8 (<https://simonsapin.github.io/wtf-8/>)
+    Wtf8,
+    /// Keep them, as UTF-16 code units
+    Utf16,
+}
+
+/// Limits on what a lexer reads, to bound the memory and the time spent on untrusted inputs.
+///
+/// Going beyond one of them fails with its own [`TokenizeError`]. The default is no limit.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Limits {
+    /// Maximum length of a string once decoded, in UTF-8 bytes (it also bounds the JSON5
+    /// identifiers and the comments kept as tokens)
+    pub string_length: usize,
+    /// Maximum number of digits of the integer part of a number (hexadecimal ones included)
+    pub int_digits: usize,
+    /// Maximum number of digits of the fractional part of a number
+    pub frac_digits: usize,
+    /// Maximum number of digits of the exponent of a number
+    pub exp_digits: usize,
+    /// Maximum number of tokens, the white spaces and the comments included
+    pub tokens: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            string_length: usize::MAX,
+            int_digits: usize::MAX,
+            frac_digits: usize::MAX,
+            exp_digits: usize::MAX,
+            tokens: usize::MAX,
+        }
+    }
+}
+
+/// The settings of a lexer.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Options {
+    pub(crate) dialect: Dialect,
+    /// Whether the comments are yielded as tokens instead of white space
+    pub(crate) comments: bool,
+    pub(crate) limits: Limits,
+    pub(crate) surrogates: Surrogates,
+}
+
+/// A lexer yielding the tokens of another one with their [`Span`] (see [`Lexer::spanned`]).
+///
+/// The span of an error covers what was read of the bad token.
+pub struct Spanned<L> {
+    lexer: L,
+}
+
+impl<'a, L: Lexer<'a>> Spanned<L> {
+    /// Returns the lexer of the tokens.
+    pub fn lexer(&self) -> &L {
+        &self.lexer
+    }
+}
+
+impl<'a, L: Lexer<'a>> Iterator for Spanned<L> {
+    type Item = (L::Item, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.lexer.current_position();
+        let t = self.lexer.next()?;
+        let end = self.lexer.current_position();
+        Some((t, Span { start, end }))
+    }
+}
+
+/// A lexer of RFC 8259 JSON, reading a [`Source`].
+///
+/// See [`crate::lexer_iter::LexerIter`], [`crate::lexer_slice::LexerSlice`] and
+/// [`crate::lexer_bytes::LexerBytes`] for the lexers of each source.
+pub struct SourceLexer<'a, Src> {
+    input: Src,
+    options: Options,
+    /// Number of tokens read
+    tokens: usize,
+    /// The errors gone past, when recovering
+    errors: Option<Vec<LexicalError>>,
+    /// Whether the end of the input or an error was reached
+    ended: bool,
+    /// The lifetime of the strings borrowed from `input`
+    strings: PhantomData<&'a str>,
+}
+
+impl<'a, Src: Source<'a>> SourceLexer<'a, Src> {
+    /// Create a new one reading `input`
+    pub fn from_source(input: Src) -> Self {
+        Self {
+            input,
+            options: Options::default(),
+            tokens: 0,
+            errors: None,
+            ended: false,
+            strings: PhantomData,
+        }
+    }
+
+    /// Reads the input as `dialect` instead of RFC 8259 JSON.
+    #[must_use]
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.options.dialect = dialect;
+        self
+    }
+
+    /// Yields the comments of the dialects having some as [`Token::Comment`]s, instead of
+    /// white space.
+    #[must_use]
+    pub fn keep_comments(mut self) -> Self {
+        self.options.comments = true;
+        self
+    }
+
+    /// Fails once the input goes beyond `limits`.
+    #[must_use]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Decodes the lone surrogate escapes of the strings following `policy`, instead of failing.
+    #[must_use]
+    pub fn with_surrogates(mut self, policy: Surrogates) -> Self {
+        self.options.surrogates = policy;
+        self
+    }
+
+    /// Goes past the lexical errors instead of ending on the first one.
+    ///
+    /// The bad token is skipped up to its closing quote (or the end of its line) if it is a
+    /// string, or else up to the next structural character, quote or white space, and the error is recorded (see
+    /// [`SourceLexer::errors`]). Only the errors of the input itself (like invalid UTF-8 or an
+    /// I/O error) and [`TokenizeError::TooManyTokens`] still end the lexing.
+    #[must_use]
+    pub fn recovering(mut self) -> Self {
+        self.errors = Some(Vec::new());
+        self
+    }
+
+    /// Returns the errors gone past so far by a recovering lexer, in the order of the input.
+    pub fn errors(&self) -> &[LexicalError] {
+        self.errors.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the source being read.
+    pub fn source(&self) -> &Src {
+        &self.input
+    }
+
+    /// Returns the source being read, to set it up before anything is read.
+    pub(crate) fn source_mut(&mut self) -> &mut Src {
+        &mut self.input
+    }
+
+    /// Skips the rest of a bad token which began at `start` with `first`.
+    fn resync(&mut self, start: Position, first: Option<char>) {
+        let quote = match first {
+            Some('"') => Some('"'),
+            Some('\'') if self.options.dialect == Dialect::Json5 => Some('\''),
+            _ => None,
+        };
+        if let Some(quote) = quote {
+            if self.input.current_position().offset == start.offset {
+                self.input.next_char();
+            }
+            // A line terminator (or any control character) can't be in a string: the string
+            // wasn't closed, and the next line must not be read as its continuation
+            let is_control = |c: Option<char>| c.is_some_and(|c| c <= '\u{1F}');
+            while !is_control(self.input.peek_char()) {
+                match self.input.next_char() {
+                    Some(c) if c == quote => return,
+                    Some('\\') if !is_control(self.input.peek_char()) => {
+                        self.input.next_char();
+                    }
+                    Some(_) => {}
+                    None => return,
+                }
+            }
+            return;
+        }
+        // A token always goes past its first char
+        if self.input.current_position().offset == start.offset {
+            self.input.next_char();
+        }
+        while let Some(c) = self.input.peek_char() {
+            if matches!(c, '[' | ']' | '{' | '}' | ':' | ',' | '"') || c.is_whitespace() {
+                return;
+            }
+            self.input.next_char();
+        }
+    }
+}
+
+impl<'a, Src: Source<'a>> Lexer<'a> for SourceLexer<'a, Src> {
+    fn current_position(&self) -> Position {
+        self.input.current_position()
+    }
+
+    fn dialect(&self) -> Dialect {
+        self.options.dialect
+    }
+}
+
+impl<'a, Src: Source<'a>> Iterator for SourceLexer<'a, Src> {
+    type Item = Result<Token<'a>, TokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.ended {
+                return None;
+            }
+            if self.tokens == self.options.limits.tokens && self.input.peek_char().is_some() {
+                self.ended = true;
+                return Some(Err(TokenizeError::TooManyTokens));
+            }
+            let start = self.input.current_position();
+            let first = self.input.peek_char();
+            match read_token(&mut self.input, &self.options) {
+                Ok(Some(t)) => {
+                    self.tokens += 1;
+                    return Some(Ok(t));
+                }
+                Err(error) if self.errors.is_some() && self.input.error().is_none() => {
+                    let position = self.input.current_position();
+                    self.errors.get_or_insert_with(Vec::new).push(LexicalError { error, position });
+                    self.resync(start, first);
+                }
+                r => {
+                    self.ended = true;
+                    // A decoding error ends the input early: it is the real cause of the failure.
+                    return match (self.input.error(), r) {
+                        (Some(e), _) => Some(Err(e.clone())),
+                        (None, Err(e)) => Some(Err(e)),
+                        (None, _) => None,
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// A lexer yielding each token with its exact text in the input, so that the concatenation of
+/// the texts is the input.
+///
+/// The texts are borrowed from the input when it can lend them (see [`Source::text`]), and
+/// copied otherwise. With a [`crate::chars::Chars`], they are the decoded chars: a skipped byte
+/// order mark isn't part of them.
+pub struct Lossless<'a, Src> {
+    lexer: SourceLexer<'a, Recorder<Src>>,
+}
+
+impl<'a, Src: Source<'a>> Lossless<'a, Src> {
+    /// Create a new one reading `input`
+    pub fn from_source(input: Src) -> Self {
+        Self {
+            lexer: SourceLexer::from_source(Recorder::new(input)),
+        }
+    }
+
+    /// Reads the input as `dialect` instead of RFC 8259 JSON.
+    #[must_use]
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.lexer = self.lexer.with_dialect(dialect);
+        self
+    }
+
+    /// Yields the comments as [`Token::Comment`]s (see [`SourceLexer::keep_comments`]).
+    #[must_use]
+    pub fn keep_comments(mut self) -> Self {
+        self.lexer = self.lexer.keep_comments();
+        self
+    }
+
+    /// Fails once the input goes beyond `limits` (see [`SourceLexer::with_limits`]).
+    #[must_use]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.lexer = self.lexer.with_limits(limits);
+        self
+    }
+
+    /// Decodes the lone surrogate escapes following `policy` (see
+    /// [`SourceLexer::with_surrogates`]).
+    #[must_use]
+    pub fn with_surrogates(mut self, policy: Surrogates) -> Self {
+        self.lexer = self.lexer.with_surrogates(policy);
+        self
+    }
+
+    /// Returns the position of the next char to be read (or where the error occured).
+    pub fn current_position(&self) -> Position {
+        self.lexer.current_position()
+    }
+}
+
+impl<'a, Src: Source<'a>> Iterator for Lossless<'a, Src> {
+    type Item = Result<LosslessToken<'a>, TokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.lexer.current_position();
+        let token = match self.lexer.next()? {
+            Ok(t) => t,
+            Err(e) => return Some(Err(e)),
+        };
+        let text = self.lexer.input.take_text(start);
+        Some(Ok(LosslessToken { token, text }))
+    }
+}
+
+const HI_SURROGATE_MIN: u32 = 0xD800;
+const HI_SURROGATE_MAX: u32 = 0xDBFF;
+const LO_SURROGATE_MIN: u32 = 0xDC00;
+const LO_SURROGATE_MAX: u32 = 0xDFFF;
+
+/// Reads a JSON token.
+pub(crate) fn read_token<'a, Src: Source<'a>>(
+    input: &mut Src,
+    options: &Options,
+) -> ReaderResult<Option<Token<'a>>> {
+    if options.dialect == Dialect::Json5 {
+        if let Some(t) = json5::read_token(input, options)? {
+            return Ok(Some(t));
+        }
+    }
+    if let Some(c) = input.peek_char() {
+        match c {
+            '/' | ' ' | '\t' | '\x0A' | '\x0D' if options.dialect == Dialect::Jsonc => {
+                read_white_space_or_comment(input, options, is_white_space).map(Some)
+            }
+            '0'..='9' | '-' => match read_number(input, &options.limits) {
+                Ok(n) => Ok(Some(Token::Number(n))),
+                Err(e) => Err(e),
+            },
+            '"' => {
+                input.next_char();
+                match read_string(input, options) {
+                    Ok(s) => Ok(Some(Token::String(s))),
+                    Err(e) => Err(e),
+                }
+            }
+            '[' => {
+                input.next_char();
+                Ok(Some(Token::ArrayBegin))
+            }
+            ']' => {
+                input.next_char();
+                Ok(Some(Token::ArrayEnd))
+            }
+            '{' => {
+                input.next_char();
+                Ok(Some(Token::ObjectBegin))
+            }
+            '}' => {
+                input.next_char();
+                Ok(Some(Token::ObjectEnd))
+            }
+            't' => {
+                input.next_char();
+                read_rue(input)?;
+                Ok(Some(Token::True))
+            }
+            'f' => {
+                input.next_char();
+                read_alse(input)?;
+                Ok(Some(Token::False))
+            }
+            'n' => {
+                input.next_char();
+                read_ull(input)?;
+                Ok(Some(Token::Null))
+            }
+            ',' => {
+                input.next_char();
+                Ok(Some(Token::ValueSeparator))
+            }
+            ':' => {
+                input.next_char();
+                Ok(Some(Token::NameSeparator))
+            }
+            ' ' | '\t' | '\x0A' | '\x0D' => {
+                input.next_char();
+                read_white_space(input)?;
+                Ok(Some(Token::WhiteSpace))
+            }
+            _ => Err(TokenizeError::UnkownToken),
+        }
+    } else {
+        Ok(None)
+    }
+}
+
+/// Whether `c` is RFC 8259 JSON white space.
+fn is_white_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\x0A' | '\x0D')
+}
+
+/// Whether `c` ends a line.
+pub(crate) fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+/// Reads white space (`is_white_space` telling which chars are) and comments, or a single
+/// [`Token::Comment`] if the comments are kept.
+pub(crate) fn read_white_space_or_comment<'a, Src: Source<'a>>(
+    input: &mut Src,
+    options: &Options,
+    is_white_space: fn(char) -> bool,
+) -> ReaderResult<Token<'a>> {
+    let start = input.current_position().offset;
+    let comments = options.comments;
+    if comments && input.peek_char() == Some('/') {
+        input.next_char();
+        let mut text = String::from("/");
+        read_comment(input, Some((&mut text, options.limits.string_length)))?;
+        let end = input.current_position().offset;
+        return Ok(Token::Comment(match input.text(start, end) {
+            Some(text) => Cow::Borrowed(text),
+            None => Cow::Owned(text),
+        }));
+    }
+    loop {
+        match input.peek_char() {
+            Some('/') if !comments => {
+                input.next_char();
+                read_comment(input, None)?;
+            }
+            Some(c) if is_white_space(c) => {
+                input.next_char();
+            }
+            _ => return Ok(Token::WhiteSpace),
+        }
+    }
+}
+
+/// Reads a `//` or `/* */` comment after its first `/`.
+///
+/// Its chars are appended to the `text` given with its maximum length, if any.
+fn read_comment<'a, Src: Source<'a>>(input: &mut Src, mut text: Option<(&mut String, usize)>) -> ReaderResult<()> {
+    let mut next_char = |input: &mut Src| {
+        let c = input.next_char();
+        if let (Some((text, limit)), Some(c)) = (&mut text, c) {
+            text.push(c);
+            if text.len() > *limit {
+                return Err(TokenizeError::StringTooLong);
+            }
+        }
+        Ok(c)
+    };
+    match next_char(input)? {
+        Some('/') => {
+            while let Some(c) = input.peek_char() {
+                if is_line_terminator(c) {
+                    break;
+                }
+                next_char(input)?;
+            }
+            Ok(())
+        }
+        Some('*') => loop {
+            match next_char(input)? {
+                Some('*') if input.peek_char() == Some('/') => {
+                    next_char(input)?;
+                    return Ok(());
+                }
+                Some(_) => {}
+                None => return Err(TokenizeError::InputEndedEarly),
+            }
+        },
+        Some(_) => Err(TokenizeError::UnkownToken),
+        None => Err(TokenizeError::InputEndedEarly),
+    }
+}
+
+/// Reads a RFC 8259 JSON number;
+fn read_number<'a, Src: Source<'a>>(input: &mut Src, limits: &Limits) -> ReaderResult<Number> {
+    let sign = read_neg_sign(input)?;
+    let int = read_int(input, limits)?;
+    let frac = read_frac(input, limits)?;
+    let exp = read_exp(input, limits)?;
+    Ok(Number::new(sign, int, frac, exp))
+}
+
+/// Reads a decimal digit.
+pub(crate) fn read_digit<'a, Src: Source<'a>>(input: &mut Src) -> ReaderResult<Digit> {
+    match input.peek_char() {
+        Some(c) => match c {
+            '0' => {
+                input.next_char();
+                Ok(Digit::D0)
+            }
+            '1' => {
+                input.next_char();
+                Ok(Digit::D1)
+            }
+            '2' => {
+                input.next_char();
+                Ok(Digit::D2)
+            }
+            '3' => {
+                input.next_char();
+                Ok(Digit::D3)
+            }
+            '4' => {
+                input.next_char();
+                Ok(Digit::D4)
+            }
+            '5' => {
+                input.next_char();
+                Ok(Digit::D5)
+            }
+            '6' => {
+                input.next_char();
+                Ok(Digit::D6)
+            }
+            '7' => {
+                input.next_char();
+                Ok(Digit::D7)
+            }
+            '8' => {
+                input.next_char();
+                Ok(Digit::D8)
+            }
+            '9' => {
+                input.next_char();
+                Ok(Digit::D9)
+            }
+            _ => Err(TokenizeError::ExpectedADigit),
+        },
+        None => Err(TokenizeError::InputEndedEarly),
+    }
+}
+
+/// Reads decimal digits after the `digits` already read, failing with `error` beyond `limit`
+/// digits.
+pub(crate) fn read_more_digits<'a, Src: Source<'a>>(
+    input: &mut Src,
+    mut digits: Vec<Digit>,
+    limit: usize,
+    error: TokenizeError,
+) -> ReaderResult<Vec<Digit>> {
+    loop {
+        if digits.len() > limit {
+            return Err(error);
+        }
+        match read_digit(input) {
+            Ok(d) => digits.push(d),
+            Err(_) => return Ok(digits),
+        }
+    }
+}
+
+/// Reads RFC 8259 JSON white space.
+fn read_white_space<'a, Src: Source<'a>>(input: &mut Src) -> ReaderResult<()> {
+    input.skip_white_space();
+    Ok(())
+}
+
+/// Reads the int part of a RFC 8259 JSON number.
+fn read_int<'a, Src: Source<'a>>(input: &mut Src, limits: &Limits) -> ReaderResult<Vec<Digit>> {
+    match read_digit(input) {
+        Ok(Digit::D0) if limits.int_digits > 0 => Ok(vec![Digit::D0]),
+        Ok(d) => read_more_digits(input, vec![d], limits.int_digits, TokenizeError::TooManyIntDigits),
+        Err(e) => Err(e),
+    }
+}
+
+/// Reads the fractional part of a RFC 8259 JSON number.
+fn read_frac<'a, Src: Source<'a>>(input: &mut Src, limits: &Limits) -> ReaderResult<Option<Vec<Digit>>> {
+    if let Some(c) = input.peek_char() {
+        if c == '.' {
+            input.next_char();
+            let first = read_digit(input)?;
+            let frac = read_more_digits(input, vec![first], limits.frac_digits, TokenizeError::TooManyFracDigits)?;
+            Ok(Some(frac))
+        } else {
+            Ok(None)
+        }
+    } else {
+        Ok(None)
+    }
+}
+
+/// Reads the exponential part of a RFC 8259 JSON number.
+pub(crate) fn read_exp<'a, Src: Source<'a>>(
+    input: &mut Src,
+    limits: &Limits,
+) -> ReaderResult<Option<(Sign, Vec<Digit>)>> {
+    match input.peek_char() {
+        Some('e' | 'E') => {
+            input.next_char();
+            let sign = read_pn_sign(input)?;
+            let first = read_digit(input)?;
+            let exp = read_more_digits(input, vec![first], limits.exp_digits, TokenizeError::TooManyExpDigits)?;
+            Ok(Some((sign, exp)))
+        }
+        Some(_) => {
+            //dbg!(c);
+            Ok(None)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Reads an optional negative sign (`'-'`).
+fn read_neg_sign<'a, Src: Source<'a>>(input: &mut Src) -> ReaderResult<Sign> {
+    match input.peek_char() {
+        Some('-') => match input.next_char() {
+            Some(_) => Ok(Sign::Negative),
+            None => Err(internal_error!()),
+        },
+        Some(_) | None => Ok(Sign::Positive),
+    }
+}
+
+/// Reads an optional positive sign (`'+'`) or a negative sign (`'-'`).
+fn read_pn_sign<'a, Src: Source<'a>>(input: &mut Src) -> ReaderResult<Sign> {
+    match input.peek_char() {
+        Some('-') => match input.next_char() {
+            Some(_) => Ok(Sign::Negative),
+            None => Err(internal_error!()),
+        },
+        Some('+') => match input.next_char() {
+            Some(_) => Ok(Sign::Positive),
+            None => Err(internal_error!()),
+        },
+        Some(_) => {
+            Ok(Sign::Positive)
+        }
+        None => Ok(Sign::Positive),
+    }
+}
+
+/// Reads RFC8259 JSON string, borrowed from the input if it has no escape sequence.
+///
+/// It fails once the string is longer than the limit of `options`.
+fn read_string<'a, Src: Source<'a>>(input: &mut Src, options: &Options) -> ReaderResult<JsonString<'a>> {
+    let limit = options.limits.string_length;
+    let mut a = String::new();
+    if let Some(run) = input.borrow_string_chars(limit) {
+        if input.peek_char() == Some('"') {
+            input.next_char();
+            return Ok(JsonString::Text(Cow::Borrowed(run)));
+        }
+        a.push_str(run);
+    }
+    let mut decoder = SurrogateDecoder::new(options.surrogates);
+    loop {
+        if decoder.len(&a) > limit {
+            return Err(TokenizeError::StringTooLong);
+        }
+        if input.peek_char() != Some('\\') {
+            decoder.flush(&mut a);
+        }
+        input.read_string_chars(&mut a, limit);
+        match input.peek_char() {
+            Some('\u{0000}'..='\u{001F}') => return Err(TokenizeError::ControlCharacterUnescaped),
+            Some('\\') => {
+                input.next_char();
+                if let Some(c) = decoder.read_escape_sequence(input, &mut a)? {
+                    a.push(c);
+                }
+            }
+            Some('"') => {
+                input.next_char();
+                return Ok(decoder.finish(a));
+            }
+            Some(c) => {
+                input.next_char();
+                a.push(c)
+            }
+            None => return Err(TokenizeError::InputEndedEarly),
+        }
+    }
+}
+
+/// Decodes the `\u` escapes of a string following a [`Surrogates`] policy.
+///
+/// The string is built as a `String`, the lone surrogates being kept aside with their offset
+/// in it until [`SurrogateDecoder::finish`].
+pub(crate) struct SurrogateDecoder {
+    policy: Surrogates,
+    /// A high surrogate waiting for its low one
+    high: Option<u16>,
+    /// The lone surrogates, with the offset where they are in the string
+    lone: Vec<(usize, u16)>,
+    /// Whether lone surrogates were replaced
+    replaced: bool,
+}
+
+impl SurrogateDecoder {
+    pub(crate) fn new(policy: Surrogates) -> Self {
+        Self {
+            policy,
+            high: None,
+            lone: Vec::new(),
+            replaced: false,
+        }
+    }
+
+    /// Returns the length of the decoded string `a`, in WTF-8 bytes.
+    pub(crate) fn len(&self, a: &str) -> usize {
+        a.len() + 3 * self.lone.len()
+    }
+
+    /// Reads an escape sequence after its `\`, returning its char unless it was a surrogate.
+    ///
+    /// With [`Surrogates::Reject`], it is [`read_escape_sequence`].
+    pub(crate) fn read_escape_sequence<'a, Src: Source<'a>>(
+        &mut self,
+        input: &mut Src,
+        a: &mut String,
+    ) -> ReaderResult<Option<char>> {
+        if self.policy == Surrogates::Reject || input.peek_char() != Some('u') {
+            self.flush(a);
+            return read_escape_sequence(input).map(Some);
+        }
+        input.next_char();
+        let unit = read_code_unit(input)?;
+        let unit = u32::from(unit);
+        if let Some(high) = self.high.take() {
+            if (LO_SURROGATE_MIN..=LO_SURROGATE_MAX).contains(&unit) {
+                let code = 0x1_0000 + ((u32::from(high) & 0x03FF) << 10) + (unit & 0x03FF);
+                return std::char::from_u32(code).map(Some).ok_or(TokenizeError::InvalidSurrogatePairs);
+            }
+            self.lone(high, a);
+        }
+        match std::char::from_u32(unit) {
+            Some(c) => Ok(Some(c)),
+            None if (HI_SURROGATE_MIN..=HI_SURROGATE_MAX).contains(&unit) => {
+                self.high = u16::try_from(unit).ok();
+                Ok(None)
+            }
+            None => {
+                self.lone(u16::try_from(unit).map_err(|_| internal_error!())?, a);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Ends the high surrogate waiting for its low one, as the string goes on without it.
+    pub(crate) fn flush(&mut self, a: &mut String) {
+        if let Some(high) = self.high.take() {
+            self.lone(high, a);
+        }
+    }
+
+    fn lone(&mut self, unit: u16, a: &mut String) {
+        if self.policy == Surrogates::Replace {
+            a.push(char::REPLACEMENT_CHARACTER);
+            self.replaced = true;
+        } else {
+            self.lone.push((a.len(), unit));
+        }
+    }
+
+    /// Returns the decoded string `a`, with its lone surrogates.
+    pub(crate) fn finish<'a>(mut self, mut a: String) -> JsonString<'a> {
+        self.flush(&mut a);
+        if self.replaced {
+            return JsonString::Replaced(a);
+        }
+        if self.lone.is_empty() {
+            return JsonString::Text(Cow::Owned(a));
+        }
+        let mut lone = self.lone.into_iter().peekable();
+        match self.policy {
+            Surrogates::Utf16 => {
+                let mut units = Vec::with_capacity(a.len());
+                for (i, c) in a.char_indices() {
+                    while let Some((_, unit)) = lone.next_if(|(offset, _)| *offset <= i) {
+                        units.push(unit);
+                    }
+                    units.extend_from_slice(c.encode_utf16(&mut [0; 2]));
+                }
+                units.extend(lone.map(|(_, unit)| unit));
+                JsonString::Utf16(units)
+            }
+            _ => {
+                let mut bytes = Vec::with_capacity(a.len() + 3);
+                let mut start = 0;
+                for (offset, unit) in lone {
+                    bytes.extend_from_slice(&a.as_bytes()[start..offset]);
+                    // The generalized UTF-8 encoding of a surrogate
+                    bytes.extend_from_slice(&[0xED, 0x80 | (unit >> 6 & 0x3F) as u8, 0x80 | (unit & 0x3F) as u8]);
+                    start = offset;
+                }
+                bytes.extend_from_slice(&a.as_bytes()[start..]);
+                JsonString::Wtf8(bytes)
+            }
+        }
+    }
+}
+
+/// Reads a "rue" (ending of "true").
+fn read_rue<'a, Src: Source<'a>>(input: &mut Src) -> ReaderResult<()> {
+    idioms::read_string(input, "rue")
+}
+
+/// Reads a "false" (ending of "false").
+fn read_alse<'a, Src: Source<'a>>(input: &mut Src) -> ReaderResult<()> {
+    idioms::read_string(input, "alse")
+}
+
+/// Reads a "ull" (ending of "null").
+fn read_ull<'a, Src: Source<'a>>(input: &mut Src) -> ReaderResult<()> {
+    idioms::read_string(input, "ull")
+}
+
+/// Reads an escape sequence (where the escaping character have already been read) from `input` according
+/// to `pos`. Escape sequences are defined in the RFC8259 as:
+///
+/// | Pattern      | Viz  |  description   |  value |
+/// |--------------|------|----------------|--------|
+/// | %x22         | "    | quotation mark | U+0022 |
+/// | %x5C         | \    | reverse solidus| U+005C |
+/// | %x2F         | /    | solidus        | U+002F |
+/// | %x62         | b    | backspace      | U+0008 |
+/// | %x66         | f    | form feed      | U+000C |
+/// | %x6E         | n    | line feed      | U+000A |
+/// | %x72         | r    | carriage return| U+000D |
+/// | %x74         | t    | tab            | U+0009 |
+/// | %x75 4HEXDIG | uXXXX|                | U+XXXX |
+pub(crate) fn read_escape_sequence<'a, Src: Source<'a>>(input: &mut Src) -> ReaderResult<char> {
+    if let Some(c) = input.next_char() {
+        match c {
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            '/' => Ok('/'),
+            'b' => Ok('\u{0008}'),
+            'f' => Ok('\u{000C}'),
+            'n' => Ok('\n'),
+            'r' => Ok('\r'),
+            't' => Ok('\t'),
+            'u' => {
+                let a = u32::from(read_code_unit(input)?);
+                if (HI_SURROGATE_MIN..=HI_SURROGATE_MAX).contains(&a) {
+                    idioms::read_string(input, "\\u")?;
+                    crate::err::debug!("Surrogating");
+                    let b = u32::from(read_code_unit(input)?);
+                    if (LO_SURROGATE_MIN..=LO_SURROGATE_MAX).contains(&b) {
+                        let mut code: u32 = 0x1_0000;
+                        code += (a & 0x03FF) << 10;
+                        code += b & 0x03FF;
+                        match std::char::from_u32(code) {
+                            Some(c) => Ok(c),
+                            None => Err(TokenizeError::InvalidSurrogatePairs),
+                        }
+                    } else {
+                        Err(TokenizeError::BigMessWithSurrogatePairs)
+                    }
+                } else {
+                    match std::char::from_u32(a) {
+                        Some(c) => Ok(c),
+                        None => Err(TokenizeError::InvalidUnicodeCodePoint),
+                    }
+                }
+            }
+            _ => Err(TokenizeError::UnkownEscapeSequence),
+        }
+    } else {
+        Err(TokenizeError::InputEndedEarly)
+    }
+}
+
+/// Reads the four hexdigits of a `\u` escape sequence.
+fn read_code_unit<'a, Src: Source<'a>>(input: &mut Src) -> ReaderResult<u16> {
+    let mut unit: u16 = 0;
+    for _ in 0..4 {
+        unit.shl_assign(4);
+        unit += u16::from(read_hexdigit(input)?);
+    }
+    Ok(unit)
+}
+
+/// Reads one characters from input and convert it to u8 considering it as an hexdigit.
+///
+/// As specified in RFC8259 a hexdigit is a character from `'0'` to `'9'` or from `'a'` to `'f'`
+/// (or from `'A'` to `'F'` since it is case insensitive).
+///
+/// # Example
+///
+/// ```ignore
+/// use libporte::lexer::read_hexdigit;
+///
+/// mut let s: Vec<char> = "2";
+/// let mut s = Chars::from(s);
+/// let r = read_hexdigitmut (&);
+/// assert_eq!(Ok((2u8, 1)), r);
+/// ```
+///
+/// A char which isn't a hexdigit isn't consumed.
+pub(crate) fn read_hexdigit<'a, Src: Source<'a>>(input: &mut Src) -> ReaderResult<u8> {
+    let d = match input.peek_char() {
+        Some(c) => match c {
+            '0'..='9' => c as u8 - b'0',
+            'a'..='f' => c as u8 - b'a' + 10_u8,
+            'A'..='F' => c as u8 - b'A' + 10_u8,
+            _ => return Err(TokenizeError::ExpectedAHexdigit),
+        },
+        None => return Err(TokenizeError::InputEndedEarly),
+    };
+    input.next_char();
+    Ok(d)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_read_x {
+        ($($func_name:ident),*) => {
+            $(
+                mod reader_interface {
+                    use super::{$func_name, owned, source};
+                    use crate::err::TokenizeError;
+                    #[test]
+                    fn empty_input(){
+                        let s = "";
+                        let s = owned(&s);
+                        let mut s = source(&s);
+                        let r = $func_name(&mut s);
+                        assert_eq!(Err(TokenizeError::InputEndedEarly), r);
+                    }
+                }
+            )*
+        };
+    }
+
+    /// Runs the same tests for each kind of source, `source` building one from what `owned` makes of
+    /// a `&str`.
+    macro_rules! shared_tests {
+        ($($name:ident: |$s:ident| -> $owned_ty:ty { $owned:expr } => |$o:ident: &$ty:ty| $source:expr;)*) => {
+            $(
+                mod $name {
+                    use super::*;
+
+                    fn owned($s: &str) -> $owned_ty {
+                        $owned
+                    }
+
+                    fn source($o: &$ty) -> impl Source<'_> + '_ {
+                        $source
+                    }
+
+                    mod read_hexdigit {
+                        use crate::err::TokenizeError;
+
+                        use crate::lexer::read_hexdigit;
+                        use super::{owned, source};
+                        use crate::source::Source;
+
+                        test_read_x! {read_hexdigit}
+
+                        #[test]
+                        fn digits() {
+                            let s = "0";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(0_u8), r);
+                            let s = "1";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(1_u8), r);
+                            let s = "2";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(2_u8), r);
+                            let s = "3";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(3_u8), r);
+                            let s = "4";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(4_u8), r);
+                            let s = "5";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(5_u8), r);
+                            let s = "6";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(6_u8), r);
+                            let s = "7";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(7_u8), r);
+                            let s = "8";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(8_u8), r);
+                            let s = "9";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(9_u8), r);
+                        }
+
+                        #[test]
+                        fn lower_case() {
+                            let s = "a";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(10_u8), r);
+                            let s = "b";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(11_u8), r);
+                            let s = "c";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(12_u8), r);
+                            let s = "d";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(13_u8), r);
+                            let s = "e";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(14_u8), r);
+                            let s = "f";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(15_u8), r);
+                        }
+
+                        #[test]
+                        fn upper_case() {
+                            let s = "A";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(10_u8), r);
+                            let s = "B";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(11_u8), r);
+                            let s = "C";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(12_u8), r);
+                            let s = "D";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(13_u8), r);
+                            let s = "E";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(14_u8), r);
+                            let s = "F";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Ok(15_u8), r);
+                        }
+
+                        #[test]
+                        fn update_position() {
+                            let s = owned("0123456789aBcDeF");
+                            let mut s = source(&s);
+                            for i in 0..16_u8 {
+                                assert_eq!(Ok(i), read_hexdigit(&mut s));
+                                assert_eq!(usize::from(i) + 1, s.current_position().offset);
+                            }
+                        }
+
+                        #[test]
+                        fn invalid() {
+                            let s = "z";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Err(TokenizeError::ExpectedAHexdigit), r);
+                            let s = "@";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Err(TokenizeError::ExpectedAHexdigit), r);
+                            let s = "One";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Err(TokenizeError::ExpectedAHexdigit), r);
+                            let s = "|ab";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Err(TokenizeError::ExpectedAHexdigit), r);
+                            let s = "\0";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Err(TokenizeError::ExpectedAHexdigit), r);
+                            let s = "\t";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_hexdigit(&mut s);
+                            assert_eq!(Err(TokenizeError::ExpectedAHexdigit), r);
+                        }
+                    }
+                    mod read_escape_sequence {
+                        use crate::lexer::read_escape_sequence;
+                        use super::{owned, source};
+                        use crate::err::TokenizeError;
+                        test_read_x! {read_escape_sequence}
+
+                        #[test]
+                        fn valid_single_chars() {
+                            let s = "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('"'), r);
+                            let s = "\\";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\\'), r);
+                            let s = "/";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('/'), r);
+                            let s = "b";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\u{0008}'), r);
+                            let s = "f";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\u{000C}'), r);
+                            let s = "n";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\n'), r);
+                            let s = "r";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\r'), r);
+                            let s = "t";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\t'), r);
+                        }
+
+                        #[test]
+                        fn invalid_single_chars() {
+                            let s = " ";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Err(TokenizeError::UnkownEscapeSequence), r);
+                            let s = "1";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Err(TokenizeError::UnkownEscapeSequence), r);
+                            let s = "a";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Err(TokenizeError::UnkownEscapeSequence), r);
+                            let s = "\n";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Err(TokenizeError::UnkownEscapeSequence), r);
+                        }
+
+                        #[test]
+                        fn valid_unicode_escape() {
+                            let s = "u0020";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok(' '), r);
+                            let s = "u0061";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('a'), r);
+                            let s = "uABCD";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\u{ABCD}'), r);
+                            let s = "uD057";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\u{D057}'), r);
+                        }
+
+                        #[test]
+                        #[ignore = "TODO: Check that there are no 4-hexdigits invalid unicode code points"]
+                        fn invalid_unicode_code_point() {
+                            // TODO: Check that there are no 4-hexdigits invalid unicode code points
+                        }
+
+                        #[test]
+                        fn unicode_invalid_too_short() {
+                            let s = "u0";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Err(TokenizeError::InputEndedEarly), r);
+                            let s = "u0 some garbage after the escape";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Err(TokenizeError::ExpectedAHexdigit), r);
+                            let s = "u";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Err(TokenizeError::InputEndedEarly), r);
+                            let s = "uFF";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Err(TokenizeError::InputEndedEarly), r);
+                        }
+
+                        #[test]
+                        fn unicode_is_exactly_4hexdigits() {
+                            let s = "u00001";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\0'), r);
+                            let s = "u000AFE";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\u{000A}'), r);
+                        }
+
+                        #[test]
+                        fn update_position() {
+                            let s = "u0000\"\\bfnrtu0000";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\0'), r);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('"'), r);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\\'), r);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\u{0008}'), r);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\u{000C}'), r);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\n'), r);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\r'), r);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\t'), r);
+                            let r = read_escape_sequence(&mut s);
+                            assert_eq!(Ok('\0'), r);
+                        }
+                    }
+                    mod read_string {
+                        use super::{owned, source};
+                        use crate::source::Source;
+                        use crate::err::TokenizeError;
+                        use crate::idioms::ReaderResult;
+
+                        fn read_string<'a>(input: &mut impl Source<'a>) -> ReaderResult<String> {
+                            crate::lexer::read_string(input, &crate::lexer::Options::default())
+                                .map(|s| s.to_string_lossy().into_owned())
+                        }
+
+                        test_read_x! {read_string}
+
+                        #[test]
+                        fn empty_string() {
+                            let s = "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Ok("".to_owned()), r);
+                            let s = "\"should have already stopped";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Ok("".to_owned()), r);
+                        }
+
+                        #[test]
+                        fn simple_strings() {
+                            let a = "Hey I'm James, how are you ?".to_string();
+                            let s = a.clone() + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Ok(a.clone()), r);
+                            let a = "I'm quite bored writing tests. &\u{e9}'(-\u{e8}__\u{e7})=$\u{f9}*".to_string();
+                            let s = a.clone() + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Ok(a.clone()), r);
+                        }
+
+                        #[test]
+                        fn u0000_through_u001f_are_invalid() {
+                            let a = "Hey I'm James, how are you ?\0".to_string();
+                            let s = a + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Err(TokenizeError::ControlCharacterUnescaped), r);
+                            let a = "Hey I'm James,\u{1} how are you ?".to_string();
+                            let s = a + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Err(TokenizeError::ControlCharacterUnescaped), r);
+                            let a = "\u{17}Hey I'm James, how are you ?".to_string();
+                            let s = a + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Err(TokenizeError::ControlCharacterUnescaped), r);
+                            let a = "\tHey I'm James, how are you ?".to_string();
+                            let s = a + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Err(TokenizeError::ControlCharacterUnescaped), r);
+                            // Other control characters (such as DEL) are valid according to RFC8259
+                            let a = "\u{7F}Hey I'm James, how are you ?".to_string();
+                            let s = a.clone() + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Ok(a.clone()), r);
+                        }
+
+                        #[test]
+                        fn some_characters_must_escaped() {
+                            let a = "Hey I'm \"James\", how are you ?".to_string();
+                            let s = a + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Ok("Hey I'm ".to_string()), r);
+                            let a = "Hey I'm \\\"James\\\", how are you ?".to_string();
+                            let s = a.clone() + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Ok("Hey I'm \"James\", how are you ?".to_string()), r);
+                            let a = "Hey I'm \\/James/\\, how are you ?".to_string();
+                            let s = a + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Err(TokenizeError::UnkownEscapeSequence), r);
+                            let a = "Hey I'm \\\\/James/\\\\, how are you ?".to_string();
+                            let s = a.clone() + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Ok("Hey I'm \\/James/\\, how are you ?".to_string()), r);
+                        }
+
+                        #[test]
+                        fn string_must_be_closed_by_quote() {
+                            let s = "Hey I'm James, how are you ?".to_string();
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Err(TokenizeError::InputEndedEarly), r);
+                        }
+
+                        #[test]
+                        fn string_with_escape_sequence() {
+                            let a = "\\t\\tSome \\\"centered\\\" line\\t\\t\\r\\n".to_string();
+                            let s = a.clone() + "\"";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Ok("\t\tSome \"centered\" line\t\t\r\n".to_string()), r);
+                        }
+
+                        #[test]
+                        fn string_in_context() {
+                            let pre = "{\"".to_string();
+                            let post = "\": 3}";
+                            let a = "\\t\\tSome \\\"centered\\\" line\\t\\t\\r\\n".to_string();
+                            let s = pre.clone() + &a + post;
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            // String starts at the second character but our function begins after
+                            // the quote, so take two items.
+                            s.next_char();
+                            s.next_char();
+                            let r = read_string(&mut s).map(String::from);
+                            assert_eq!(Ok("\t\tSome \"centered\" line\t\t\r\n".to_string()), r);
+                            let s: Vec<char> = std::iter::from_fn(|| s.next_char()).collect();
+                            let end: Vec<char> = post.chars().skip(1usize).collect();
+                            assert_eq!(s, end)
+                        }
+                    }
+                    mod read_number {
+                        use std::vec;
+
+                        use super::{owned, source};
+                        use crate::idioms::ReaderResult;
+                        use crate::lexer::Limits;
+                        use crate::source::Source;
+
+                        fn read_number<'a>(input: &mut impl Source<'a>) -> ReaderResult<Number> {
+                            crate::lexer::read_number(input, &Limits::default())
+                        }
+
+                        use crate::number::Digit::{D0, D1, D2, D3, D4, D5, D6, D7, D8, D9};
+                        use crate::number::{Exp, Number, Sign};
+                        use crate::err::TokenizeError;
+                        #[test]
+                        fn some_positive_int() {
+                            let s = "123";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Positive,
+                                int: vec![D1, D2, D3],
+                                frac: None,
+                                exp: None,
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                            let s = "1789654320";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Positive,
+                                int: vec![D1, D7, D8, D9, D6, D5, D4, D3, D2, D0],
+                                frac: None,
+                                exp: None,
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                        }
+
+                        #[test]
+                        fn some_negative_int() {
+                            let s = "-103";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Negative,
+                                int: vec![D1, D0, D3],
+                                frac: None,
+                                exp: None,
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                            let s = "-1789654320";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Negative,
+                                int: vec![D1, D7, D8, D9, D6, D5, D4, D3, D2, D0],
+                                frac: None,
+                                exp: None,
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                        }
+
+                        #[test]
+                        fn some_invalid_positive_int() {
+                            let s = "+123";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            assert_eq!(Err(TokenizeError::ExpectedADigit), read_number(&mut s));
+                            let s = "+1789654320";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            assert_eq!(Err(TokenizeError::ExpectedADigit), read_number(&mut s));
+                        }
+
+                        #[test]
+                        fn leading_zero() {
+                            let s = "0123";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Positive,
+                                int: vec![D0],
+                                frac: None,
+                                exp: None,
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                            let s = "-01789654320";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Negative,
+                                int: vec![D0],
+                                frac: None,
+                                exp: None,
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                        }
+
+                        #[test]
+                        fn with_fraction() {
+                            let s = "3.141592653589793";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Positive,
+                                int: vec![D3],
+                                frac: Some(vec![
+                                    D1, D4, D1, D5, D9, D2, D6, D5, D3, D5, D8, D9, D7, D9, D3,
+                                ]),
+                                exp: None,
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                            let s = "-0.5";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Negative,
+                                int: vec![D0],
+                                frac: Some(vec![D5]),
+                                exp: None,
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                        }
+
+                        #[test]
+                        fn with_lower_case_exp() {
+                            let s = "6022e20";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Positive,
+                                int: vec![D6, D0, D2, D2],
+                                frac: None,
+                                exp: Some(Exp {
+                                    s: Sign::Positive,
+                                    v: vec![D2, D0],
+                                }),
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                        }
+
+                        #[test]
+                        fn with_upper_case_exp() {
+                            let s = "1602E-22";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Positive,
+                                int: vec![D1, D6, D0, D2],
+                                frac: None,
+                                exp: Some(Exp {
+                                    s: Sign::Negative,
+                                    v: vec![D2, D2],
+                                }),
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                        }
+
+                        #[test]
+                        fn sign_frac_exp() {
+                            let s = "6.022E+22";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Positive,
+                                int: vec![D6],
+                                frac: Some(vec![D0, D2, D2]),
+                                exp: Some(Exp {
+                                    s: Sign::Positive,
+                                    v: vec![D2, D2],
+                                }),
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                            let s = "-1.602e-19";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let n = Number {
+                                sign: Sign::Negative,
+                                int: vec![D1],
+                                frac: Some(vec![D6, D0, D2]),
+                                exp: Some(Exp {
+                                    s: Sign::Negative,
+                                    v: vec![D1, D9],
+                                }),
+                            };
+                            assert_eq!(Ok(n), read_number(&mut s));
+                        }
+
+                        #[test]
+                        fn invalid_inputs() {
+                            let s = "d23f";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            assert_eq!(Err(TokenizeError::ExpectedADigit), read_number(&mut s));
+                            let s = "NaN";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            assert_eq!(Err(TokenizeError::ExpectedADigit), read_number(&mut s));
+                            let s = "Infinity";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            assert_eq!(Err(TokenizeError::ExpectedADigit), read_number(&mut s));
+                            let s = "three";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            assert_eq!(Err(TokenizeError::ExpectedADigit), read_number(&mut s));
+                            let s = "e";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            assert_eq!(Err(TokenizeError::ExpectedADigit), read_number(&mut s));
+                            let s = "MDCCLXXXIX";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            assert_eq!(Err(TokenizeError::ExpectedADigit), read_number(&mut s));
+                        }
+                    }
+
+                    mod read_white_space {
+        
+                        use crate::lexer::read_white_space;
+                        use super::{owned, source};
+                        use crate::source::Source;
+                        #[test]
+                        fn read_just_what_needed() {
+                            let s = "    a";
+                            let s = owned(&s);
+                            let mut s = source(&s);
+                            let _ = read_white_space(&mut s);
+                            let s: Vec<char> = std::iter::from_fn(|| s.next_char()).collect();
+                            assert_eq!(s, vec!['a']);
+                        }
+                    }
+
+                    #[test]
+                    fn error_position() {
+                        let text = owned("[1,\n  x]");
+                        let mut l = SourceLexer::from_source(source(&text));
+                        assert_eq!(Some(Err(TokenizeError::UnkownToken)), l.by_ref().find(Result::is_err));
+                        assert_eq!(None, l.next());
+                        let p = l.current_position();
+                        assert_eq!((6, 2, 3), (p.offset, p.line, p.column));
+                    }
+
+                    #[test]
+                    fn spans() {
+                        let text = owned("{\"é\": [1,\n true]}");
+                        let spans: Vec<(Token, (usize, usize, usize), (usize, usize, usize))> =
+                            SourceLexer::from_source(source(&text))
+                                .spanned()
+                                .map(|(t, s)| {
+                                    let (b, e) = (s.start, s.end);
+                                    (t.unwrap(), (b.offset, b.line, b.column), (e.offset, e.line, e.column))
+                                })
+                                .collect();
+                        assert_eq!(
+                            vec![
+                                (Token::ObjectBegin, (0, 1, 1), (1, 1, 2)),
+                                (Token::String("é".into()), (1, 1, 2), (5, 1, 5)),
+                                (Token::NameSeparator, (5, 1, 5), (6, 1, 6)),
+                                (Token::WhiteSpace, (6, 1, 6), (7, 1, 7)),
+                                (Token::ArrayBegin, (7, 1, 7), (8, 1, 8)),
+                                (Token::Number(Number::new(Sign::Positive, vec![Digit::D1], None, None)), (8, 1, 8), (9, 1, 9)),
+                                (Token::ValueSeparator, (9, 1, 9), (10, 1, 10)),
+                                (Token::WhiteSpace, (10, 1, 10), (12, 2, 2)),
+                                (Token::True, (12, 2, 2), (16, 2, 6)),
+                                (Token::ArrayEnd, (16, 2, 6), (17, 2, 7)),
+                                (Token::ObjectEnd, (17, 2, 7), (18, 2, 8)),
+                            ],
+                            spans
+                        );
+                    }
+
+                    #[test]
+                    fn lossless() {
+                        let input = " {\"\\u0041\" :\t[1E5, -0.50e+1, \"é\\n\", true,\r\n null ] }\n";
+                        let text = owned(input);
+                        let tokens: Vec<LosslessToken> =
+                            Lossless::from_source(source(&text)).map(Result::unwrap).collect();
+                        let text: String = tokens.iter().map(|t| t.text.as_ref()).collect();
+                        assert_eq!(input, text);
+                        let text = owned(input);
+                        let expected: Vec<Token> =
+                            SourceLexer::from_source(source(&text)).map(Result::unwrap).collect();
+                        assert_eq!(expected, tokens.into_iter().map(|t| t.token).collect::<Vec<_>>());
+                        let text = owned("[1, x]");
+                        let mut l = Lossless::from_source(source(&text));
+                        assert_eq!(Some(Err(TokenizeError::UnkownToken)), l.by_ref().find(Result::is_err));
+                        assert_eq!(None, l.next());
+                    }
+
+                    #[test]
+                    fn end_of_input() {
+                        let text = owned("[1]");
+                        let mut l = SourceLexer::from_source(source(&text));
+                        assert_eq!(3, l.by_ref().map(Result::unwrap).count());
+                        assert_eq!(None, l.next());
+                        let text = owned("[\"a");
+                        let mut l = SourceLexer::from_source(source(&text));
+                        assert_eq!(Some(Ok(Token::ArrayBegin)), l.next());
+                        assert_eq!(Some(Err(TokenizeError::InputEndedEarly)), l.next());
+                        assert_eq!(None, l.next());
+                    }
+
+                    #[test]
+                    fn jsonc() {
+                        let input = "// one\n[1, /* two */ 2,]";
+                        let text = owned(input);
+                        let tokens: Vec<Token> = SourceLexer::from_source(source(&text))
+                            .with_dialect(Dialect::Jsonc)
+                            .map(Result::unwrap)
+                            .collect();
+                        assert_eq!(Token::WhiteSpace, tokens[0]);
+                        assert_eq!(Token::ArrayBegin, tokens[1]);
+                        assert_eq!(Token::WhiteSpace, tokens[4]);
+                        assert_eq!(Token::ArrayEnd, tokens[7]);
+                        let text = owned(input);
+                        let comments: Vec<Token> = SourceLexer::from_source(source(&text))
+                            .with_dialect(Dialect::Jsonc)
+                            .keep_comments()
+                            .map(Result::unwrap)
+                            .filter(|t| matches!(t, Token::Comment(_)))
+                            .collect();
+                        assert_eq!(vec![Token::Comment("// one".into()), Token::Comment("/* two */".into())], comments);
+                        let text = owned(input);
+                        let text: String = Lossless::from_source(source(&text))
+                            .with_dialect(Dialect::Jsonc)
+                            .keep_comments()
+                            .map(|t| t.unwrap().text)
+                            .collect();
+                        assert_eq!(input, text);
+                        let text = owned("[/* 1");
+                        let mut l = SourceLexer::from_source(source(&text)).with_dialect(Dialect::Jsonc);
+                        assert_eq!(Some(Err(TokenizeError::InputEndedEarly)), l.by_ref().find(Result::is_err));
+                        let text = owned("[/ 1");
+                        let mut l = SourceLexer::from_source(source(&text)).with_dialect(Dialect::Jsonc);
+                        assert_eq!(Some(Err(TokenizeError::UnkownToken)), l.by_ref().find(Result::is_err));
+                        let text = owned("[1, 'a']");
+                        let mut l = SourceLexer::from_source(source(&text)).with_dialect(Dialect::Jsonc);
+                        assert_eq!(Some(Err(TokenizeError::UnkownToken)), l.by_ref().find(Result::is_err));
+                    }
+
+                    #[test]
+                    fn limits() {
+                        let limits = Limits {
+                            string_length: 4,
+                            int_digits: 3,
+                            frac_digits: 2,
+                            exp_digits: 1,
+                            tokens: 7,
+                        };
+                        let last = |s| {
+                            let text = owned(s);
+                            SourceLexer::from_source(source(&text)).with_limits(limits).last().map(|t| t.map(Token::into_owned))
+                        };
+                        assert_eq!(Some(Ok(Token::ArrayEnd)), last("[\"a€\", 123.45e6]"));
+                        assert_eq!(Some(Err(TokenizeError::StringTooLong)), last("[\"abcde\"]"));
+                        assert_eq!(Some(Err(TokenizeError::StringTooLong)), last("[\"ab\\n€\"]"));
+                        assert_eq!(Some(Err(TokenizeError::StringTooLong)), last("[\"abc€\"]"));
+                        assert_eq!(Some(Err(TokenizeError::TooManyIntDigits)), last("[-1234]"));
+                        assert_eq!(Some(Err(TokenizeError::TooManyFracDigits)), last("[0.123]"));
+                        assert_eq!(Some(Err(TokenizeError::TooManyExpDigits)), last("[1E+12]"));
+                        assert_eq!(Some(Err(TokenizeError::TooManyTokens)), last("[1, 2, 3]"));
+                        let text = owned("/* long */");
+                        let comment = SourceLexer::from_source(source(&text))
+                            .with_dialect(Dialect::Jsonc)
+                            .keep_comments()
+                            .with_limits(limits)
+                            .last();
+                        assert_eq!(Some(Err(TokenizeError::StringTooLong)), comment);
+                        let text = owned("[\"abcdefgh\"]");
+                        let mut l = SourceLexer::from_source(source(&text)).with_limits(limits);
+                        assert_eq!(Some(Err(TokenizeError::StringTooLong)), l.by_ref().last());
+                        assert_eq!(7, l.current_position().offset);
+                    }
+
+                    #[test]
+                    fn surrogates() {
+                        let string = |s, policy| match SourceLexer::from_source(source(&owned(s))).with_surrogates(policy).next() {
+                            Some(Ok(Token::String(s))) => Ok(s.into_owned()),
+                            Some(Err(e)) => Err(e),
+                            t => unreachable!("{}: {:?}", s, t),
+                        };
+                        let lone_low = r#""a\uDC00b""#;
+                        let lone_high = r#""\uD834\u0041\uD834\uDD1E\uD834""#;
+                        let high_then_char = r#""é\uD834\n""#;
+                        assert_eq!(Err(TokenizeError::InvalidUnicodeCodePoint), string(lone_low, Surrogates::Reject));
+                        assert_eq!(Err(TokenizeError::BigMessWithSurrogatePairs), string(lone_high, Surrogates::Reject));
+                        assert_eq!(Err(TokenizeError::LitteralDidntMatch), string(high_then_char, Surrogates::Reject));
+                        assert_eq!(Ok(JsonString::Replaced("a\u{FFFD}b".into())), string(lone_low, Surrogates::Replace));
+                        assert_eq!(
+                            Ok(JsonString::Replaced("\u{FFFD}A\u{1D11E}\u{FFFD}".into())),
+                            string(lone_high, Surrogates::Replace)
+                        );
+                        assert_eq!(Ok(JsonString::Wtf8(b"a\xED\xB0\x80b".to_vec())), string(lone_low, Surrogates::Wtf8));
+                        assert_eq!(
+                            Ok(JsonString::Wtf8(b"\xC3\xA9\xED\xA0\xB4\n".to_vec())),
+                            string(high_then_char, Surrogates::Wtf8)
+                        );
+                        assert_eq!(Ok(JsonString::Utf16(vec![0x61, 0xDC00, 0x62])), string(lone_low, Surrogates::Utf16));
+                        assert_eq!(
+                            Ok(JsonString::Utf16(vec![0xD834, 0x41, 0xD834, 0xDD1E, 0xD834])),
+                            string(lone_high, Surrogates::Utf16)
+                        );
+                        for policy in [Surrogates::Replace, Surrogates::Wtf8, Surrogates::Utf16] {
+                            assert_eq!(Ok(JsonString::Text("\u{1D11E}".into())), string(r#""\uD834\uDD1E""#, policy));
+                            assert_eq!(Err(TokenizeError::ExpectedAHexdigit), string(r#""\uD83x""#, policy));
+                        }
+                    }
+
+                    #[test]
+                    fn recovering() {
+                        let input = "[tru, \"a\\xb\", 1.e5, @x, \"ok\" 2]\n";
+                        let text = owned(input);
+                        let mut l = SourceLexer::from_source(source(&text)).recovering();
+                        let tokens: Vec<Token> = l.by_ref().map(Result::unwrap).filter(|t| *t != Token::WhiteSpace).collect();
+                        assert_eq!(
+                            vec![
+                                Token::ArrayBegin,
+                                Token::ValueSeparator,
+                                Token::ValueSeparator,
+                                Token::ValueSeparator,
+                                Token::ValueSeparator,
+                                Token::String("ok".into()),
+                                Token::Number(Number::new(Sign::Positive, vec![Digit::D2], None, None)),
+                                Token::ArrayEnd,
+                            ],
+                            tokens
+                        );
+                        let errors: Vec<(TokenizeError, usize)> =
+                            l.errors().iter().map(|e| (e.error.clone(), e.position.offset)).collect();
+                        assert_eq!(
+                            vec![
+                                (TokenizeError::LitteralDidntMatch, 4),
+                                (TokenizeError::UnkownEscapeSequence, 10),
+                                (TokenizeError::ExpectedADigit, 16),
+                                (TokenizeError::UnkownToken, 20),
+                            ],
+                            errors
+                        );
+                        let text = owned("[\"a\\u12\", \"b");
+                        let mut l = SourceLexer::from_source(source(&text)).recovering();
+                        assert_eq!(3, l.by_ref().map(Result::unwrap).count());
+                        let errors: Vec<&TokenizeError> = l.errors().iter().map(|e| &e.error).collect();
+                        assert_eq!(vec![&TokenizeError::ExpectedAHexdigit, &TokenizeError::InputEndedEarly], errors);
+                        let input = "{\"a\": \"unterminated,\n \"b\": \"ok\",\n \"c\": 1}";
+                        let text = owned(input);
+                        let mut l = SourceLexer::from_source(source(&text)).recovering();
+                        let strings: Vec<Token> =
+                            l.by_ref().map(Result::unwrap).filter(|t| matches!(t, Token::String(_))).collect();
+                        let expected: Vec<Token> = ["a", "b", "ok", "c"].iter().map(|&s| Token::String(s.into())).collect();
+                        assert_eq!(expected, strings);
+                        let errors: Vec<(TokenizeError, usize)> =
+                            l.errors().iter().map(|e| (e.error.clone(), e.position.offset)).collect();
+                        assert_eq!(vec![(TokenizeError::ControlCharacterUnescaped, 20)], errors);
+                        let text = owned("[1, x]");
+                        let mut l = SourceLexer::from_source(source(&text));
+                        assert!(l.by_ref().any(|t| t.is_err()));
+                        assert!(l.errors().is_empty());
+                    }
+
+                    #[should_panic]
+                    #[test]
+                    fn complete_test() {
+                        let s = "{\"asd\": { \"sdf\" : [123, 3.14]}}";
+                        let s = owned(&s);
+                        let mut s = source(&s);
+                        let mut r = Vec::<Token>::new();
+                        while let Ok(Some(t)) = read_token(&mut s, &Options::default()) {
+                            r.push(t);
+                        }
+                        assert_eq!(Vec::<Token>::new(), r)
+                    }
+                }
+            )*
+        };
+    }
+
+    shared_tests! {
+        chars: |s| -> String { s.to_owned() } => |text: &str| crate::chars::Chars::from(text);
+        char_slice: |s| -> Vec<char> { s.chars().collect() } => |text: &[char]| crate::source::CharSlice::new(text);
+        byte_slice: |s| -> String { s.to_owned() } => |text: &str| crate::source::ByteSlice::new(text.as_bytes());
+        str_slice: |s| -> String { s.to_owned() } => |text: &str| crate::source::ByteSlice::from(text);
+    }
+}
@@ -0,0 +1,169 @@
+//! The lexer reading a text borrowed as bytes, without copying it.
+
+use crate::{chars::BomPolicy, lexer::SourceLexer, source::ByteSlice};
+
+/// A lexer reading a borrowed UTF-8 input in place (see [`ByteSlice`]).
+///
+/// The tokens are the same as the ones of [`crate::lexer_iter::LexerIter`] reading the same
+/// input as UTF-8, except that the strings without escape sequences are borrowed from the input.
+pub type LexerBytes<'a> = SourceLexer<'a, ByteSlice<'a>>;
+
+impl<'a> LexerBytes<'a> {
+    /// Create a new one, checking that `input` is valid UTF-8 while reading it.
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::from_source(ByteSlice::new(input))
+    }
+
+    /// Sets what to do with a leading byte order mark (see [`ByteSlice::with_bom_policy`]).
+    #[must_use]
+    pub fn with_bom_policy(mut self, bom_policy: BomPolicy) -> Self {
+        self.source_mut().set_bom_policy(bom_policy);
+        self
+    }
+}
+
+impl<'a> From<&'a str> for LexerBytes<'a> {
+    fn from(input: &'a str) -> Self {
+        Self::from_source(ByteSlice::from(input))
+    }
+}
+
+impl<'a> From<&'a [u8]> for LexerBytes<'a> {
+    fn from(input: &'a [u8]) -> Self {
+        Self::new(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use crate::{chars::{BomPolicy, Chars, Encoding}, err::{TokenizeError, Utf8Error}, lexer::{Lexer, Lossless}, lexer_iter::LexerIter, token::{JsonString, Token}};
+
+    const DOCUMENTS: [&[u8]; 22] = [
+        r#"{"a": [1, -2.5e+3, true, false, null], "bé\"\\\/\b\f\n\r\t": {"c": "d𝄞"}}"#.as_bytes(),
+        "  [\"é€𝄞\", \"\", 0, 0.125e-10, 1E5]\r\n".as_bytes(),
+        b"[tru]",
+        b"nul",
+        b"[01]",
+        b"-",
+        b"1.",
+        b"1e+",
+        b"\"abc",
+        b"\"a\x01\"",
+        br#""\x""#,
+        br#""\u12""#,
+        br#""\ud834A""#,
+        br#""\ud834x""#,
+        b"\xEF\xBB\xBF[]",
+        b"[1, \xFF]",
+        b"\"a\xE0\x80\"",
+        b"\"a\xC3\"",
+        b"\"a\xE2\x82",
+        b"tr\xC3",
+        b"1.5e\xF0\x9F",
+        "[1] é".as_bytes(),
+    ];
+
+    /// Lexes `input` with `lexer`, returning the tokens and the error it stopped on.
+    fn lex<'a>(lexer: impl Lexer<'a>) -> Vec<Result<Token<'a>, TokenizeError>> {
+        lexer.collect()
+    }
+
+    #[test]
+    fn same_tokens_as_lexer_iter() {
+        for document in DOCUMENTS {
+            // Every prefix of the documents ends in an interesting place
+            for end in 0..=document.len() {
+                let document = &document[..end];
+                let chars = Chars::new(document.iter().copied()).with_encoding(Encoding::Utf8);
+                let expected = lex(LexerIter::new(chars));
+                assert_eq!(expected, lex(LexerBytes::new(document)), "{:?}", document);
+                if let Ok(text) = std::str::from_utf8(document) {
+                    assert_eq!(expected, lex(LexerBytes::from(text)), "{:?}", text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn long_runs() {
+        let document = format!("[\r\n{0}\"{1}\",{0}\t\"{1}\\n{1}é\"\n\n{0}x]", " ".repeat(70), "a".repeat(70));
+        let chars = Chars::new(document.bytes()).with_encoding(Encoding::Utf8);
+        let mut expected = LexerIter::new(chars);
+        let mut l = LexerBytes::from(document.as_str());
+        assert_eq!(expected.by_ref().collect::<Vec<_>>(), l.by_ref().collect::<Vec<_>>());
+        assert_eq!(expected.current_position(), l.current_position());
+    }
+
+    #[test]
+    fn bom_policy() {
+        let bytes = b"\xEF\xBB\xBF[1]";
+        let mut l = LexerBytes::new(bytes);
+        assert_eq!(Some(Err(TokenizeError::ByteOrderMark)), l.next());
+        assert_eq!(None, l.next());
+        let mut l = LexerBytes::new(bytes).with_bom_policy(BomPolicy::Skip);
+        assert_eq!(3, l.by_ref().map(Result::unwrap).count());
+        assert_eq!(6, l.current_position().offset);
+        assert!(!l.source().had_bom());
+        let mut l = LexerBytes::from("\u{FEFF}[1]").with_bom_policy(BomPolicy::Report);
+        assert_eq!(Some(Ok(Token::ArrayBegin)), l.next());
+        assert_eq!((4, 1, 2), (l.current_position().offset, l.current_position().line, l.current_position().column));
+        assert_eq!(2, l.by_ref().map(Result::unwrap).count());
+        assert!(l.source().had_bom());
+    }
+
+    #[test]
+    fn utf8_errors() {
+        assert_eq!(Some(Err(TokenizeError::InvalidUTF8(Utf8Error::Surrogate))), lex(LexerBytes::new(b"[\"\xED\xA0\x80\"]")).pop());
+        assert_eq!(Some(Err(TokenizeError::InvalidUTF8(Utf8Error::Overlong))), lex(LexerBytes::new(b"[\xC0\xAF]")).pop());
+        assert_eq!(
+            Some(Err(TokenizeError::InvalidUTF8(Utf8Error::UnexpectedContinuationByte))),
+            lex(LexerBytes::new(b"[\x80]")).pop()
+        );
+    }
+
+    #[test]
+    fn recovering_stops_on_invalid_utf8() {
+        let mut l = LexerBytes::new(b"[x, \xFF, 1]").recovering();
+        assert_eq!(Some(Err(TokenizeError::InvalidUTF8(Utf8Error::OutOfRange))), l.by_ref().last());
+        assert_eq!(1, l.errors().len());
+    }
+
+    #[test]
+    fn borrowed_strings() {
+        let document = r#"["abc", "", "é€𝄞", "d\u00e9f", "g\n"]"#;
+        let strings: Vec<Cow<str>> = LexerBytes::new(document.as_bytes())
+            .filter_map(|t| match t {
+                Ok(Token::String(JsonString::Text(s))) => Some(s),
+                _ => None,
+            })
+            .collect();
+        let borrowed: Vec<bool> = strings.iter().map(|s| matches!(s, Cow::Borrowed(_))).collect();
+        assert_eq!(vec![true, true, true, false, false], borrowed);
+        assert_eq!(vec!["abc", "", "é€𝄞", "déf", "g\n"], strings);
+    }
+
+    #[test]
+    fn lossless_texts_are_borrowed() {
+        let input = "[\"a\\u0041\", 1E5]";
+        for t in Lossless::from_source(ByteSlice::from(input)) {
+            assert!(matches!(t.unwrap().text, Cow::Borrowed(_)));
+        }
+        for t in Lossless::from_source(ByteSlice::new(input.as_bytes())) {
+            assert!(matches!(t.unwrap().text, Cow::Borrowed(_)));
+        }
+    }
+
+    #[test]
+    fn position() {
+        let mut l = LexerBytes::from("[\"é\",\n  x]");
+        assert_eq!(Some(Err(TokenizeError::UnkownToken)), l.by_ref().last());
+        let p = l.current_position();
+        assert_eq!((9, 2, 3), (p.offset, p.line, p.column));
+        let mut l = LexerBytes::from("\"é\" ");
+        while l.next().is_some() {}
+        let p = l.current_position();
+        assert_eq!((5, 1, 5), (p.offset, p.line, p.column));
+    }
+}
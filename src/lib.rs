@@ -1,15 +1,18 @@
 //#![deny(missing_docs)]
 //! A crate for parsing JSON
 #![deny(clippy::panic, clippy::missing_panics_doc)]
-#![feature(type_alias_impl_trait)]
-mod idioms_iter;
-mod idioms_slice;
+mod float;
+mod idioms;
+mod json5;
+pub mod lexer_bytes;
 pub mod lexer_iter;
 pub mod lexer_slice;
-mod lexer;
-mod number;
-mod token;
-mod value;
+pub mod lexer;
+pub mod source;
+pub mod number;
+mod scan;
+pub mod token;
+pub mod value;
 /// Functions to parse a JSON text
 pub mod ast;
 /// A representation for bufferized char reading
@@ -18,3 +21,13 @@ pub mod chars;
 pub mod err;
 /// A stack-based automaton to read a stream of Tokens.
 pub mod automaton;
+/// Incremental lexing and parsing of an input given chunk by chunk
+pub mod push;
+/// Writing of tokens as JSON text
+pub mod writer;
+/// Lexing of memory-mapped files
+#[cfg(feature = "mmap")]
+pub mod mmap;
+/// Asynchronous reading of the input
+#[cfg(feature = "async")]
+pub mod asynchronous;
@@ -0,0 +1,287 @@
+//! Lexing of memory-mapped files.
+
+use std::{convert::TryFrom, fs::File, io, iter::Copied, path::Path, slice};
+
+use memmap2::{Mmap, MmapOptions};
+
+use crate::{
+    chars::{BomPolicy, Chars, Encoding, Position},
+    err::TokenizeError,
+    lexer::{Dialect, Lexer, Limits, Surrogates},
+    lexer_bytes::LexerBytes,
+    lexer_iter::LexerIter,
+    token::Token,
+};
+
+/// A file mapped in memory, to be lexed in place by a [`LexerBytes`].
+///
+/// The encoding is guessed from the first bytes like [`Chars`] does: a UTF-16 or UTF-32 file is
+/// decoded by a [`LexerIter`] over the mapped bytes instead.
+///
+/// Only the length the file had when it was opened is mapped. Reading a mapped page which is
+/// no longer backed by the file kills the process (`SIGBUS`), so the file is checked not to be
+/// shorter before and after it is lexed: a truncation is then reported as an I/O error. It
+/// can't be detected while the lexer is running, if another process truncates the file then.
+pub struct MappedFile {
+    file: File,
+    /// The length of the file when it was opened
+    len: u64,
+    /// `None` for an empty file, which can't be mapped
+    map: Option<Mmap>,
+}
+
+impl MappedFile {
+    /// Maps the file at `path`.
+    ///
+    /// # Errors
+    /// Returns the error met opening or mapping the file.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let map = if len == 0 {
+            None
+        } else {
+            let map_len = usize::try_from(len)
+                .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "the file is too large to be mapped"))?;
+            // SAFETY: the mapping is only read, and only `len` bytes are mapped. The file may
+            // still be modified by another process: the length is checked again by `check`.
+            Some(unsafe { MmapOptions::new().len(map_len).map(&file)? })
+        };
+        Ok(Self { file, len, map })
+    }
+
+    /// Returns the mapped bytes.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.map.as_deref().unwrap_or_default()
+    }
+
+    /// Checks that the file is still at least as long as when it was mapped.
+    ///
+    /// # Errors
+    /// Returns an I/O error of kind `UnexpectedEof` if the file was truncated.
+    pub fn check(&self) -> io::Result<()> {
+        if self.file.metadata()?.len() < self.len {
+            Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the file was truncated while being read",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns a lexer over the mapped bytes.
+    #[must_use]
+    pub fn lexer(&self) -> MappedLexer<'_> {
+        let bytes = self.as_bytes();
+        let mut chars = Chars::new(bytes.iter().copied());
+        chars.peek();
+        let lexer = match chars.encoding() {
+            Some(Encoding::Utf8) | None => Inner::Utf8(LexerBytes::new(bytes)),
+            Some(_) => Inner::Decoded(LexerIter::new(Chars::new(bytes.iter().copied()))),
+        };
+        MappedLexer {
+            file: self,
+            lexer,
+            started: false,
+            ended: false,
+        }
+    }
+}
+
+/// A lexer of a [`MappedFile`], checking that it wasn't truncated.
+pub struct MappedLexer<'a> {
+    file: &'a MappedFile,
+    lexer: Inner<'a>,
+    started: bool,
+    ended: bool,
+}
+
+/// The lexer of the mapped bytes, reading them in place when they are UTF-8.
+enum Inner<'a> {
+    Utf8(LexerBytes<'a>),
+    Decoded(LexerIter<Copied<slice::Iter<'a, u8>>>),
+}
+
+impl MappedLexer<'_> {
+    /// Reads the given dialect of JSON (see [`crate::lexer::SourceLexer::with_dialect`]).
+    #[must_use]
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.lexer = match self.lexer {
+            Inner::Utf8(l) => Inner::Utf8(l.with_dialect(dialect)),
+            Inner::Decoded(l) => Inner::Decoded(l.with_dialect(dialect)),
+        };
+        self
+    }
+
+    /// Fails once the input goes beyond `limits` (see [`crate::lexer::SourceLexer::with_limits`]).
+    #[must_use]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.lexer = match self.lexer {
+            Inner::Utf8(l) => Inner::Utf8(l.with_limits(limits)),
+            Inner::Decoded(l) => Inner::Decoded(l.with_limits(limits)),
+        };
+        self
+    }
+
+    /// Decodes the lone surrogate escapes following `policy` (see
+    /// [`crate::lexer::SourceLexer::with_surrogates`]).
+    #[must_use]
+    pub fn with_surrogates(mut self, policy: Surrogates) -> Self {
+        self.lexer = match self.lexer {
+            Inner::Utf8(l) => Inner::Utf8(l.with_surrogates(policy)),
+            Inner::Decoded(l) => Inner::Decoded(l.with_surrogates(policy)),
+        };
+        self
+    }
+
+    /// Sets what to do with a UTF-8 byte order mark (see [`LexerBytes::with_bom_policy`]). The
+    /// byte order marks of the other encodings are always skipped.
+    #[must_use]
+    pub fn with_bom_policy(mut self, bom_policy: BomPolicy) -> Self {
+        if let Inner::Utf8(l) = self.lexer {
+            self.lexer = Inner::Utf8(l.with_bom_policy(bom_policy));
+        }
+        self
+    }
+
+    /// Ends the lexing with the error returned by the check of the file, if any.
+    fn check(&mut self) -> Result<(), TokenizeError> {
+        self.file.check().map_err(|e| {
+            self.ended = true;
+            e.into()
+        })
+    }
+}
+
+impl<'a> Lexer<'a> for MappedLexer<'a> {
+    fn current_position(&self) -> Position {
+        match &self.lexer {
+            Inner::Utf8(l) => l.current_position(),
+            Inner::Decoded(l) => l.current_position(),
+        }
+    }
+
+    fn dialect(&self) -> Dialect {
+        match &self.lexer {
+            Inner::Utf8(l) => l.dialect(),
+            Inner::Decoded(l) => l.dialect(),
+        }
+    }
+}
+
+impl<'a> Iterator for MappedLexer<'a> {
+    type Item = Result<Token<'a>, TokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ended {
+            return None;
+        }
+        if !self.started {
+            self.started = true;
+            if let Err(e) = self.check() {
+                return Some(Err(e));
+            }
+        }
+        let next = match &mut self.lexer {
+            Inner::Utf8(l) => l.next(),
+            Inner::Decoded(l) => l.next(),
+        };
+        match next {
+            Some(Ok(t)) => Some(Ok(t)),
+            // Bytes read past the end of a truncated file are zeros: the truncation is the real
+            // cause of the failure.
+            Some(Err(e)) => {
+                self.ended = true;
+                Some(Err(self.check().err().unwrap_or(e)))
+            }
+            None => {
+                self.ended = true;
+                self.check().err().map(Err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{automaton::{Action, Automaton}, lexer_iter::LexerIter, chars::Chars};
+
+    /// A file removed when dropped.
+    struct TempFile(std::path::PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, content: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(format!("porte-{}-{}", std::process::id(), name));
+            std::fs::write(&path, content).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn same_tokens_as_lexer_iter() {
+        let document = r#"{"a": [1, -2.5e+3, true, false, null], "bé": {"c": "d𝄞"}}"#;
+        let file = TempFile::new("same", document.as_bytes());
+        let mapped = MappedFile::open(&file.0).unwrap();
+        let tokens: Vec<_> = mapped.lexer().collect();
+        assert_eq!(LexerIter::new(Chars::from(document)).collect::<Vec<_>>(), tokens);
+        assert!(tokens.iter().all(Result::is_ok));
+    }
+
+    #[test]
+    fn other_encodings() {
+        let document = "[1, \"é𝄞\"]";
+        let expected: Vec<_> = LexerIter::new(Chars::from(document)).collect();
+        let utf16: Vec<u8> = document.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let file = TempFile::new("utf16", &[&[0xFF, 0xFE], utf16.as_slice()].concat());
+        let mapped = MappedFile::open(&file.0).unwrap();
+        assert_eq!(expected, mapped.lexer().collect::<Vec<_>>());
+        let file = TempFile::new("utf16-no-bom", &utf16);
+        let mapped = MappedFile::open(&file.0).unwrap();
+        assert_eq!(expected, mapped.lexer().collect::<Vec<_>>());
+        let file = TempFile::new("bom", &[Encoding::Utf8.bom(), document.as_bytes()].concat());
+        let mapped = MappedFile::open(&file.0).unwrap();
+        assert_eq!(Some(Err(TokenizeError::ByteOrderMark)), mapped.lexer().next());
+        assert_eq!(expected, mapped.lexer().with_bom_policy(BomPolicy::Skip).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn empty_file() {
+        let file = TempFile::new("empty", b"");
+        let mapped = MappedFile::open(&file.0).unwrap();
+        let expected = Automaton::new(LexerIter::new(Chars::from(""))).next();
+        assert_eq!(expected, Automaton::new(mapped.lexer()).next());
+    }
+
+    #[test]
+    fn truncated_file() {
+        let file = TempFile::new("truncated", b"[1, 2, 3]");
+        let mapped = MappedFile::open(&file.0).unwrap();
+        std::fs::write(&file.0, b"[1").unwrap();
+        let mut automaton = Automaton::new(mapped.lexer());
+        match automaton.next() {
+            Some(Err(TokenizeError::Io(e))) => assert_eq!(io::ErrorKind::UnexpectedEof, e.kind()),
+            r => unreachable!("{:?}", r),
+        }
+        assert_eq!(None, automaton.next());
+    }
+
+    #[test]
+    fn truncated_while_lexing() {
+        let file = TempFile::new("while", b"[1, 2, 3]");
+        let mapped = MappedFile::open(&file.0).unwrap();
+        let mut automaton = Automaton::new(mapped.lexer());
+        assert_eq!(Some(Ok(Action::NewArray)), automaton.next());
+        std::fs::OpenOptions::new().write(true).open(&file.0).unwrap().set_len(2).unwrap();
+        let end = automaton.by_ref().last();
+        assert!(matches!(end, Some(Err(TokenizeError::Io(_)))), "{:?}", end);
+    }
+}
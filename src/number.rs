@@ -1,6 +1,8 @@
 //! A representation for the JSON numbers (that could be arbitrarly large).
 
-use std::convert::TryFrom;
+use std::{cmp::Ordering, convert::TryFrom, fmt};
+
+use crate::float::{self, Float};
 
 /// Representation of a decimal number.
 /// It must have a `sign` and a non-empty `int` part (with no meaningless leading zeros).
@@ -37,15 +39,149 @@ impl Number {
         }
     }
 
-    /// Transforms a Number to the canonic scientific notation
+    /// Creates a new integer from its hexadecimal digits (most significant first).
+    pub(crate) fn from_hex_digits(sign: Sign, hex: &[u8]) -> Self {
+        // The decimal digits, least significant first
+        let mut decimal: Vec<u8> = Vec::new();
+        for &h in hex {
+            let mut carry = h;
+            for d in &mut decimal {
+                let x = *d * 16 + carry;
+                *d = x % 10;
+                carry = x / 10;
+            }
+            while carry > 0 {
+                decimal.push(carry % 10);
+                carry /= 10;
+            }
+        }
+        if decimal.is_empty() {
+            decimal.push(0);
+        }
+        let int = decimal.iter().rev().map(|&d| Digit::from_value(d)).collect();
+        Self::new(sign, int, None, None)
+    }
+
+    /// Transforms a Number to the canonic scientific notation: a single non-zero integer digit,
+    /// a fractional part without trailing zeros, and an exponent unless it is zero.
+    ///
+    /// Zero becomes `0`, without sign, fractional part nor exponent.
     pub fn scientific_notation(&mut self) {
-        clear_leading_zeros(&mut self.int);
-        let new_exp = &self.exp;
-        if self.int[0] == Digit::D0 {
+        let int_len = self.int.len();
+        let mut digits = std::mem::take(&mut self.int);
+        digits.extend(self.frac.take().unwrap_or_default());
+        let first = match digits.iter().position(|d| d != &Digit::D0) {
+            Some(first) => first,
+            None => {
+                *self = Self::new(Sign::Positive, vec![Digit::D0], None, None);
+                return;
+            }
+        };
+        let last = digits.iter().rposition(|d| d != &Digit::D0).unwrap_or(first);
+        digits.truncate(last + 1);
+        let frac = digits.split_off(first + 1);
+        digits.drain(..first);
+        self.int = digits;
+        self.frac = Some(frac).filter(|frac| !frac.is_empty());
+        // The point moves from after the integer digits to after the first non-zero one
+        self.exp = if first < int_len {
+            add_to_exp(self.exp.take(), Sign::Positive, int_len - 1 - first)
+        } else {
+            add_to_exp(self.exp.take(), Sign::Negative, first + 1 - int_len)
+        };
+    }
+}
 
+/// Writes the number as a RFC 8259 JSON number.
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.sign == Sign::Negative {
+            f.write_str("-")?;
         }
-        todo!()
+        write_digits(f, &self.int)?;
+        if let Some(frac) = self.frac.as_ref().filter(|frac| !frac.is_empty()) {
+            f.write_str(".")?;
+            write_digits(f, frac)?;
+        }
+        if let Some(exp) = &self.exp {
+            f.write_str(if exp.s == Sign::Negative { "e-" } else { "e" })?;
+            write_digits(f, &exp.v)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_digits(f: &mut fmt::Formatter<'_>, digits: &[Digit]) -> fmt::Result {
+    let digits: String = digits.iter().map(Digit::to_char).collect();
+    f.write_str(&digits)
+}
+
+/// Adds `n`, with its `sign`, to `exp`, which can have any number of digits.
+/// A zero exponent is `None`.
+fn add_to_exp(exp: Option<Exp>, sign: Sign, n: usize) -> Option<Exp> {
+    let (mut s, mut v) = match exp {
+        Some(Exp { s, v }) if !v.is_empty() => (s, v),
+        _ => (Sign::Positive, vec![Digit::D0]),
+    };
+    clear_leading_zeros(&mut v);
+    let n = to_digits(n);
+    if s == sign {
+        v = add_digits(&v, &n);
+    } else if cmp_digits(&v, &n) == Ordering::Less {
+        v = sub_digits(&n, &v);
+        s = sign;
+    } else {
+        v = sub_digits(&v, &n);
     }
+    clear_leading_zeros(&mut v);
+    Some(Exp { s, v }).filter(|exp| exp.v != [Digit::D0])
+}
+
+/// Returns the decimal digits of `n`.
+fn to_digits(n: usize) -> Vec<Digit> {
+    n.to_string().bytes().map(|b| Digit::from_value(b - b'0')).collect()
+}
+
+/// Compares two magnitudes without leading zeros.
+fn cmp_digits(a: &[Digit], b: &[Digit]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Adds two magnitudes.
+fn add_digits(a: &[Digit], b: &[Digit]) -> Vec<Digit> {
+    let (mut a, mut b) = (a.iter().rev(), b.iter().rev());
+    let mut r = Vec::new();
+    let mut carry = 0;
+    loop {
+        let (x, y) = match (a.next(), b.next()) {
+            (None, None) => break,
+            (x, y) => (x.map_or(0, Digit::value), y.map_or(0, Digit::value)),
+        };
+        let sum = x + y + carry;
+        r.push(Digit::from_value(sum % 10));
+        carry = sum / 10;
+    }
+    if carry > 0 {
+        r.push(Digit::D1);
+    }
+    r.reverse();
+    r
+}
+
+/// Subtracts the magnitude `b` from `a`, which must not be smaller. The result can have leading
+/// zeros.
+fn sub_digits(a: &[Digit], b: &[Digit]) -> Vec<Digit> {
+    let mut b = b.iter().rev();
+    let mut r = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for x in a.iter().rev() {
+        let y = b.next().map_or(0, Digit::value) + borrow;
+        let (d, next) = if x.value() >= y { (x.value() - y, 0) } else { (x.value() + 10 - y, 1) };
+        r.push(Digit::from_value(d));
+        borrow = next;
+    }
+    r.reverse();
+    r
 }
 
 fn clear_leading_zeros(v: &mut Vec<Digit>) -> usize {
@@ -64,18 +200,148 @@ fn clear_leading_zeros(v: &mut Vec<Digit>) -> usize {
     }
 }
 
-impl TryFrom<Number> for u64 {
-    type Error = ();
+/// Why a [`Number`] can't be converted to a primitive type.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ConversionError {
+    /// The number has a fractional part
+    NotInteger,
+    /// The number is out of the range of the type
+    TooLarge,
+    /// The number isn't zero, but is too close to zero for the type
+    TooPrecise,
+}
+
+/// What the conversions to floats do with the numbers out of their range.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum OutOfRange {
+    /// Fail with [`ConversionError::TooLarge`] or [`ConversionError::TooPrecise`]
+    #[default]
+    Fail,
+    /// Return an infinity or a zero, with the sign of the number
+    Saturate,
+}
+
+impl Number {
+    /// Returns the significant digits (from the first non-zero one to the last non-zero one),
+    /// their count and the power of 10 of the last one, unless the number is zero.
+    fn significand(&self) -> Option<(impl Iterator<Item = u8> + '_, usize, i128)> {
+        let frac = self.frac.as_deref().unwrap_or_default();
+        let digits = self.int.iter().chain(frac);
+        let first = digits.clone().position(|d| d != &Digit::D0)?;
+        let zeros = digits.clone().rev().position(|d| d != &Digit::D0).unwrap_or_default();
+        let last = self.int.len() + frac.len() - 1 - zeros;
+        let power = self.exp_value() + self.int.len() as i128 - 1 - last as i128;
+        Some((digits.take(last + 1).skip(first).map(Digit::value), last + 1 - first, power))
+    }
 
-    fn try_from(value: Number) -> Result<Self, Self::Error> {
-        todo!()
+    /// Returns whether the number is negative, and its magnitude if it is an integer of at most
+    /// 128 bits.
+    fn to_integer(&self) -> Result<(bool, u128), ConversionError> {
+        let (digits, count, zeros) = match self.significand() {
+            Some(significand) => significand,
+            None => return Ok((false, 0)),
+        };
+        if zeros < 0 {
+            return Err(ConversionError::NotInteger);
+        }
+        // u128::MAX has 39 digits
+        if count as i128 + zeros > 39 {
+            return Err(ConversionError::TooLarge);
+        }
+        let magnitude = digits
+            .chain((0..zeros).map(|_| 0))
+            .try_fold(0u128, |m, d| m.checked_mul(10)?.checked_add(d.into()))
+            .ok_or(ConversionError::TooLarge)?;
+        Ok((self.sign == Sign::Negative, magnitude))
+    }
+
+    /// Returns the `f64` nearest to the number (ties to even).
+    ///
+    /// # Errors
+    /// Fails with [`ConversionError::TooLarge`] when it is infinite, and with
+    /// [`ConversionError::TooPrecise`] when it is zero but the number isn't, unless `out_of_range`
+    /// saturates.
+    pub fn to_f64(&self, out_of_range: OutOfRange) -> Result<f64, ConversionError> {
+        self.to_float(out_of_range)
+    }
+
+    /// Returns the `f32` nearest to the number (ties to even).
+    ///
+    /// # Errors
+    /// Fails like [`Number::to_f64`].
+    pub fn to_f32(&self, out_of_range: OutOfRange) -> Result<f32, ConversionError> {
+        self.to_float(out_of_range)
+    }
+
+    fn to_float<F: Float>(&self, out_of_range: OutOfRange) -> Result<F, ConversionError> {
+        let f = match self.significand() {
+            Some((digits, count, power)) => match float::to_float(digits, count, power) {
+                Err(ConversionError::TooLarge) if out_of_range == OutOfRange::Saturate => Ok(F::INFINITY),
+                Err(ConversionError::TooPrecise) if out_of_range == OutOfRange::Saturate => Ok(F::ZERO),
+                f => f,
+            },
+            None => Ok(F::ZERO),
+        };
+        match self.sign {
+            Sign::Positive => f,
+            Sign::Negative => f.map(|f| -f),
+        }
+    }
+
+    /// Returns the value of the exponent, saturated far beyond the lengths of the digits.
+    fn exp_value(&self) -> i128 {
+        let exp = match &self.exp {
+            Some(exp) => exp,
+            None => return 0,
+        };
+        let v = exp
+            .v
+            .iter()
+            .try_fold(0i128, |v, d| Some(v * 10 + i128::from(d.value())).filter(|&v| v < 1 << 80))
+            .unwrap_or(1 << 80);
+        match exp.s {
+            Sign::Positive => v,
+            Sign::Negative => -v,
+        }
     }
 }
 
-enum ConversionError {
-    NotInteger,
-    TooLarge,
-    TooPrecise,
+macro_rules! try_from_number {
+    ($($t:ty),*) => {
+        $(
+            impl TryFrom<&Number> for $t {
+                type Error = ConversionError;
+
+                fn try_from(n: &Number) -> Result<Self, Self::Error> {
+                    match n.to_integer()? {
+                        (false, m) => <$t>::try_from(m).map_err(|_| ConversionError::TooLarge),
+                        (true, m) => {
+                            let v = 0i128.checked_sub_unsigned(m).ok_or(ConversionError::TooLarge)?;
+                            <$t>::try_from(v).map_err(|_| ConversionError::TooLarge)
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+try_from_number!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl TryFrom<&Number> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(n: &Number) -> Result<Self, Self::Error> {
+        n.to_f64(OutOfRange::Fail)
+    }
+}
+
+impl TryFrom<&Number> for f32 {
+    type Error = ConversionError;
+
+    fn try_from(n: &Number) -> Result<Self, Self::Error> {
+        n.to_f32(OutOfRange::Fail)
+    }
 }
 
 /// Enum representation of a sign (either positive or negative)
@@ -87,7 +353,7 @@ pub(crate) enum Sign {
 }
 
 /// Representation of decimal digit.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone)]
 #[allow(missing_docs)]
 pub(crate) enum Digit {
     D0,
@@ -102,9 +368,329 @@ pub(crate) enum Digit {
     D9,
 }
 
+impl Digit {
+    /// Returns the digit of value `d`, which must be below 10.
+    pub(crate) fn from_value(d: u8) -> Self {
+        match d {
+            0 => Digit::D0,
+            1 => Digit::D1,
+            2 => Digit::D2,
+            3 => Digit::D3,
+            4 => Digit::D4,
+            5 => Digit::D5,
+            6 => Digit::D6,
+            7 => Digit::D7,
+            8 => Digit::D8,
+            _ => Digit::D9,
+        }
+    }
+
+    /// Returns the value of the digit.
+    pub(crate) fn value(&self) -> u8 {
+        self.clone() as u8
+    }
+
+    /// Returns the ASCII char of the digit.
+    pub(crate) fn to_char(&self) -> char {
+        char::from(b'0' + self.value())
+    }
+}
+
 /// Representation of an exponent
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub(crate) struct Exp {
     pub(crate) s: Sign,
     pub(crate) v: Vec<Digit>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer_bytes::LexerBytes, token::Token};
+
+    /// Lexes the number `s`.
+    fn number(s: &str) -> Number {
+        match LexerBytes::from(s).next() {
+            Some(Ok(Token::Number(n))) => n,
+            t => unreachable!("{:?}", t),
+        }
+    }
+
+    #[test]
+    fn scientific_notation() {
+        let cases = [
+            ("0", "0"),
+            ("-0.000e-5", "0"),
+            ("1", "1"),
+            ("10", "1e1"),
+            ("123", "1.23e2"),
+            ("-0.00120", "-1.2e-3"),
+            ("1.5e-1", "1.5e-1"),
+            ("15e-1", "1.5"),
+            ("0.01e2", "1"),
+            ("100e-2", "1"),
+            ("1e-0", "1"),
+            ("1E+007", "1e7"),
+            ("-12.3400e-0002", "-1.234e-1"),
+            ("0.5e-3", "5e-4"),
+            ("9.99e-2", "9.99e-2"),
+        ];
+        for (input, expected) in cases.iter() {
+            let mut n = number(input);
+            n.scientific_notation();
+            assert_eq!(*expected, n.to_string(), "{}", input);
+            n.scientific_notation();
+            assert_eq!(*expected, n.to_string(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn large_exponents() {
+        let cases = [
+            ("1e99999999999999999999999999", "1e99999999999999999999999999"),
+            ("1234e99999999999999999999999998", "1.234e100000000000000000000000001"),
+            ("0.001e-99999999999999999999999999", "1e-100000000000000000000000002"),
+            ("0.01e100000000000000000000000001", "1e99999999999999999999999999"),
+            ("1000e-100000000000000000000000002", "1e-99999999999999999999999999"),
+            ("0.1e1", "1"),
+            ("1e-1", "1e-1"),
+            ("10e-1", "1"),
+            ("1.23e-1", "1.23e-1"),
+        ];
+        for (input, expected) in cases.iter() {
+            let mut n = number(input);
+            n.scientific_notation();
+            assert_eq!(*expected, n.to_string(), "{}", input);
+        }
+        let mut n = number(&format!("1{}e-99", "0".repeat(100)));
+        n.scientific_notation();
+        assert_eq!("1e1", n.to_string());
+    }
+
+    #[test]
+    fn integers() {
+        let convert = |s| u64::try_from(&number(s));
+        assert_eq!(Ok(1000), convert("1e3"));
+        assert_eq!(Ok(15), convert("1.50e1"));
+        assert_eq!(Ok(0), convert("-0.0e-7"));
+        assert_eq!(Ok(12), convert("1200e-2"));
+        assert_eq!(Ok(7), convert("0.00007e00005"));
+        assert_eq!(Ok(u64::MAX), convert("18446744073709551615"));
+        assert_eq!(Ok(u64::MAX), convert("1.8446744073709551615e19"));
+        assert_eq!(Err(ConversionError::TooLarge), convert("18446744073709551616"));
+        assert_eq!(Err(ConversionError::TooLarge), convert("-1"));
+        assert_eq!(Err(ConversionError::NotInteger), convert("1.5"));
+        assert_eq!(Err(ConversionError::NotInteger), convert("15e-1"));
+        assert_eq!(Err(ConversionError::NotInteger), convert("1e-99999999999999999999999999"));
+        assert_eq!(Err(ConversionError::TooLarge), convert("1e400"));
+        assert_eq!(Err(ConversionError::TooLarge), convert("1e99999999999999999999999999"));
+        assert_eq!(Err(ConversionError::TooLarge), convert("1.5e99999999999999999999999999"));
+    }
+
+    #[test]
+    fn integer_types() {
+        assert_eq!(Ok(255), u8::try_from(&number("255")));
+        assert_eq!(Err(ConversionError::TooLarge), u8::try_from(&number("256")));
+        assert_eq!(Ok(-128), i8::try_from(&number("-128")));
+        assert_eq!(Err(ConversionError::TooLarge), i8::try_from(&number("128")));
+        assert_eq!(Err(ConversionError::TooLarge), i8::try_from(&number("-129")));
+        assert_eq!(Ok(-32768), i16::try_from(&number("-3.2768e4")));
+        assert_eq!(Ok(u32::MAX), u32::try_from(&number(&u32::MAX.to_string())));
+        assert_eq!(Ok(i64::MIN), i64::try_from(&number(&i64::MIN.to_string())));
+        assert_eq!(Ok(usize::MAX), usize::try_from(&number(&usize::MAX.to_string())));
+        assert_eq!(Ok(isize::MIN), isize::try_from(&number(&isize::MIN.to_string())));
+        assert_eq!(Ok(u128::MAX), u128::try_from(&number(&u128::MAX.to_string())));
+        assert_eq!(Err(ConversionError::TooLarge), u128::try_from(&number("340282366920938463463374607431768211456")));
+        assert_eq!(Ok(i128::MIN), i128::try_from(&number(&i128::MIN.to_string())));
+        assert_eq!(Ok(i128::MAX), i128::try_from(&number(&i128::MAX.to_string())));
+        assert_eq!(Err(ConversionError::TooLarge), i128::try_from(&number("-170141183460469231731687303715884105729")));
+        assert_eq!(Ok(10u128.pow(38)), u128::try_from(&number("1e38")));
+        assert_eq!(Err(ConversionError::TooLarge), u128::try_from(&number("1e39")));
+    }
+
+    /// A xorshift generator.
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// Returns a number in `0..n`.
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        /// Returns `len` random decimal digits.
+        fn digits(&mut self, len: u64) -> String {
+            (0..len).map(|_| char::from(b'0' + self.below(10) as u8)).collect()
+        }
+
+        /// Returns a JSON number, often out of the range of the floats or with many digits.
+        fn number(&mut self) -> String {
+            let mut s = String::new();
+            if self.below(2) == 0 {
+                s.push('-');
+            }
+            let int_len = [1, self.below(20), self.below(40), self.below(1000)][self.below(4) as usize];
+            if int_len <= 1 {
+                s.push_str(&self.digits(1));
+            } else {
+                s.push(char::from(b'1' + self.below(9) as u8));
+                s.push_str(&self.digits(int_len - 1));
+            }
+            if self.below(2) == 0 {
+                let frac_len = [1 + self.below(20), 1 + self.below(400)][self.below(2) as usize];
+                s.push('.');
+                s.push_str(&self.digits(frac_len));
+            }
+            if self.below(4) != 0 {
+                let exp = [self.below(30), self.below(60), self.below(400), self.below(1200)][self.below(4) as usize];
+                s.push_str(if self.below(2) == 0 { "e-" } else { "e" });
+                s.push_str(&exp.to_string());
+            }
+            s
+        }
+    }
+
+    /// Checks the conversions of `s` against the ones of the standard library.
+    fn check_floats(s: &str) {
+        let n = number(s);
+        let expected_f64: f64 = s.parse().unwrap();
+        let expected_f32: f32 = s.parse().unwrap();
+        let is_zero = n.significand().is_none();
+        let expected = |f: f64| {
+            if f.is_infinite() {
+                Err(ConversionError::TooLarge)
+            } else if f == 0.0 && !is_zero {
+                Err(ConversionError::TooPrecise)
+            } else {
+                Ok(f.to_bits())
+            }
+        };
+        assert_eq!(expected(expected_f64), n.to_f64(OutOfRange::Fail).map(f64::to_bits), "{}", s);
+        assert_eq!(Ok(expected_f64.to_bits()), n.to_f64(OutOfRange::Saturate).map(f64::to_bits), "{}", s);
+        let expected = expected(f64::from(expected_f32)).map(|_| expected_f32.to_bits());
+        assert_eq!(expected, n.to_f32(OutOfRange::Fail).map(f32::to_bits), "{}", s);
+        assert_eq!(Ok(expected_f32.to_bits()), n.to_f32(OutOfRange::Saturate).map(f32::to_bits), "{}", s);
+    }
+
+    /// Returns the decimal digits of `m * 2^e`, and their exponent.
+    fn exact_decimal(m: u128, e: i32) -> (String, i32) {
+        // Limbs of 9 digits, least significant first
+        let mut limbs = Vec::new();
+        let mut m = m;
+        while m > 0 {
+            limbs.push((m % 1_000_000_000) as u64);
+            m /= 1_000_000_000;
+        }
+        let (factor, mut times) = if e < 0 { (5u64, -e) } else { (2, e) };
+        while times > 0 {
+            let n = times.min(12);
+            times -= n;
+            let mut carry = 0;
+            for limb in &mut limbs {
+                let x = *limb * factor.pow(n as u32) + carry;
+                *limb = x % 1_000_000_000;
+                carry = x / 1_000_000_000;
+            }
+            while carry > 0 {
+                limbs.push(carry % 1_000_000_000);
+                carry /= 1_000_000_000;
+            }
+        }
+        let digits: String = limbs.iter().rev().map(|limb| format!("{:09}", limb)).collect();
+        (digits.trim_start_matches('0').to_string(), e.min(0))
+    }
+
+    #[test]
+    fn floats() {
+        let cases = [
+            "0",
+            "-0",
+            "1",
+            "-1.5",
+            "0.1",
+            "123456789012345678901234567890",
+            "1e22",
+            "1e23",
+            "9007199254740993",
+            "2.2250738585072011e-308",
+            "2.2250738585072012e-308",
+            "4.9e-324",
+            "2.4703282292062327e-324",
+            "2.4703282292062328e-324",
+            "1e-400",
+            "1.7976931348623157e308",
+            "1.7976931348623158e308",
+            "1.7976931348623159e308",
+            "-1e400",
+            "3.4028235e38",
+            "3.4028236e38",
+            "1.4e-45",
+            "7e-46",
+            "7.1e-46",
+        ];
+        for s in cases.iter() {
+            check_floats(s);
+        }
+        assert_eq!(Err(ConversionError::TooLarge), f64::try_from(&number("1e99999999999999999999999999")));
+        assert_eq!(Err(ConversionError::TooPrecise), f32::try_from(&number("-1e-99999999999999999999999999")));
+        assert_eq!(Ok(f64::NEG_INFINITY), number("-1e99999999999999999999999999").to_f64(OutOfRange::Saturate));
+        assert_eq!(Ok(0.0), number("0e99999999999999999999999999").to_f64(OutOfRange::Fail));
+        let long = format!("0.{}1e1", "0".repeat(10000));
+        assert_eq!(Ok(0.0), number(&long).to_f64(OutOfRange::Saturate));
+        let long = format!("1.{}1", "0".repeat(10000));
+        assert_eq!(Ok(1.0), number(&long).to_f64(OutOfRange::Fail));
+    }
+
+    /// Checks the conversions of `count` random numbers, and of `halfway` numbers halfway between
+    /// two floats or very close.
+    fn against_parse(count: usize, halfway: usize) {
+        let mut random = Random(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..count {
+            check_floats(&random.number());
+        }
+        for i in 0..halfway {
+            // Floats of all exponents, the subnormal ones included
+            let bits = random.below(0x7FF0_0000_0000_0000);
+            let (exp, fraction) = ((bits >> 52) as i32, bits & ((1 << 52) - 1));
+            let (m, e) = if exp == 0 { (fraction, -1074) } else { (fraction | 1 << 52, exp - 1075) };
+            check_halfway(m, e);
+            let bits32 = random.below(0x7F80_0000);
+            let (exp, fraction) = ((bits32 >> 23) as i32, bits32 & ((1 << 23) - 1));
+            let (m, e) = if exp == 0 { (fraction, -149) } else { (fraction | 1 << 23, exp - 150) };
+            check_halfway(m, e);
+            if i % 16 == 0 {
+                check_floats(&format!("{:e}", f64::from_bits(bits)));
+                check_floats(&format!("{:e}", f32::from_bits(bits32 as u32)));
+            }
+        }
+    }
+
+    /// Checks the numbers halfway between the float `m * 2^e` and the next one, and very close.
+    fn check_halfway(m: u64, e: i32) {
+        let halfway = u128::from(2 * m + 1);
+        let (digits, exp) = exact_decimal(halfway, e - 1);
+        check_floats(&format!("{}e{}", digits, exp));
+        // Just below and just above
+        for near in [halfway * 10u128.pow(20) - 1, halfway * 10u128.pow(20) + 1].iter() {
+            let (digits, exp) = exact_decimal(*near, e - 1);
+            check_floats(&format!("{}e{}", digits, exp - 20));
+        }
+    }
+
+    #[test]
+    fn floats_against_parse() {
+        against_parse(30_000, 3_000);
+    }
+
+    #[test]
+    #[ignore = "slow: run with `cargo test --release -- --ignored`"]
+    fn millions_of_floats_against_parse() {
+        against_parse(3_000_000, 300_000);
+    }
+}
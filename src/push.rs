@@ -0,0 +1,529 @@
+//! Incremental lexing and parsing of an input given chunk by chunk.
+
+use crate::{
+    automaton::{Action, Machine},
+    chars::{Chars, Encoding, Position},
+    err::{internal_error, TokenizeError, Utf8Error},
+    lexer::{Lexer, Limits},
+    lexer_iter::LexerIter,
+    token::Token,
+};
+
+/// Whether the input fed so far may end there.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Status {
+    /// Some bytes wait for the next chunks to complete a token (or the document)
+    NeedMoreInput,
+    /// Everything fed so far was turned into tokens (or into a whole document)
+    Complete,
+}
+
+/// A lexer to which the input is pushed chunk by chunk.
+///
+/// The bytes of a token which isn't complete at the end of a chunk are kept, and lexed again
+/// once a later chunk may complete it: a chunk may end anywhere, even in the middle of a UTF-8
+/// sequence or of an escape sequence. The bytes of a long string, number or white space are only
+/// scanned once for its end before it is lexed. The input must be UTF-8.
+pub struct PushLexer {
+    /// Bytes of the incomplete token
+    pending: Vec<u8>,
+    /// How far the pending bytes were scanned for the end of their token
+    scan: Scan,
+    /// Position of the first pending byte
+    position: Position,
+    limits: Limits,
+    /// Number of tokens lexed
+    tokens: usize,
+    status: Option<Result<(), TokenizeError>>,
+    /// Number of bytes scanned or lexed, to check that each byte is only examined a few times
+    #[cfg(test)]
+    examined: usize,
+}
+
+impl PushLexer {
+    /// Create a new one
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            scan: Scan::default(),
+            position: Position::default(),
+            limits: Limits::default(),
+            tokens: 0,
+            status: None,
+            #[cfg(test)]
+            examined: 0,
+        }
+    }
+
+    /// Fails once the input goes beyond `limits`.
+    #[must_use]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Lexes a new `chunk` of the input, and pushes the tokens it completed to `tokens`.
+    ///
+    /// # Errors
+    /// Returns the lexical error found in the input (again and again once it was found).
+    pub fn feed(&mut self, chunk: &[u8], tokens: &mut Vec<Token<'static>>) -> Result<Status, TokenizeError> {
+        self.check()?;
+        self.pending.extend_from_slice(chunk);
+        if !self.may_end() {
+            return Ok(Status::NeedMoreInput);
+        }
+        self.lex(false, tokens)
+    }
+
+    /// Signals the end of the input, and pushes the last tokens to `tokens`.
+    ///
+    /// # Errors
+    /// Returns the lexical error found in the input, for example if it ends in the middle of a
+    /// string.
+    pub fn finish(&mut self, tokens: &mut Vec<Token<'static>>) -> Result<(), TokenizeError> {
+        self.check()?;
+        self.lex(true, tokens)?;
+        self.status = Some(Ok(()));
+        Ok(())
+    }
+
+    /// Returns why the lexer stopped, `None` if it is still waiting for input.
+    #[must_use]
+    pub fn report(&self) -> &Option<Result<(), TokenizeError>> {
+        &self.status
+    }
+
+    /// Returns the position of the first byte not lexed yet (or where the error occured).
+    #[must_use]
+    pub fn current_position(&self) -> Position {
+        self.position
+    }
+
+    /// Whether the pending bytes can only be white spaces.
+    fn only_white_space_pending(&self) -> bool {
+        // The white spaces which are followed by something else are lexed
+        self.pending.first().is_none_or(|b| is_white_space(*b))
+    }
+
+    /// Scans the bytes fed since the last scan, telling whether the pending token may end (or
+    /// fail) in them: lexing it again before is useless.
+    fn may_end(&mut self) -> bool {
+        let new = &self.pending[self.scan.scanned.min(self.pending.len())..];
+        #[cfg(test)]
+        {
+            self.examined += new.len();
+        }
+        let ends = match self.pending.first() {
+            Some(b'"') => {
+                let escape = &mut self.scan.escape;
+                new.iter().any(|&b| match b {
+                    _ if *escape => {
+                        *escape = false;
+                        false
+                    }
+                    b'\\' => {
+                        *escape = true;
+                        false
+                    }
+                    _ => b == b'"' || b < 0x20,
+                })
+            }
+            Some(b'-' | b'0'..=b'9') => new.iter().any(|b| !matches!(b, b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-')),
+            Some(&b) if is_white_space(b) => new.iter().any(|&b| !is_white_space(b)),
+            _ => true,
+        };
+        self.scan.scanned = self.pending.len();
+        ends || self.pending.len() > self.longest_pending()
+    }
+
+    /// Returns a length beyond which the pending token surely goes beyond the limits.
+    fn longest_pending(&self) -> usize {
+        let limits = &self.limits;
+        match self.pending.first() {
+            // An escape sequence of 6 bytes makes at least a byte of the string
+            Some(b'"') => limits.string_length.saturating_mul(6).saturating_add(2),
+            Some(b'-' | b'0'..=b'9') => limits
+                .int_digits
+                .saturating_add(limits.frac_digits)
+                .saturating_add(limits.exp_digits)
+                .saturating_add(4),
+            _ => usize::MAX,
+        }
+    }
+
+    fn check(&self) -> Result<(), TokenizeError> {
+        match &self.status {
+            None => Ok(()),
+            Some(Err(e)) => Err(e.clone()),
+            Some(Ok(())) => Err(TokenizeError::InputTooLong),
+        }
+    }
+
+    /// Lexes the pending bytes, keeping the last token if it may not be complete.
+    fn lex(&mut self, last: bool, tokens: &mut Vec<Token<'static>>) -> Result<Status, TokenizeError> {
+        let len = self.pending.len();
+        #[cfg(test)]
+        {
+            self.examined += len;
+        }
+        let chars = Chars::new(self.pending.iter().copied());
+        // A byte order mark is only looked for at the beginning
+        let chars = if self.position.offset == 0 {
+            chars.with_encoding(Encoding::Utf8)
+        } else {
+            chars.resumed()
+        };
+        // The tokens are counted here, across the chunks
+        let limits = Limits {
+            tokens: usize::MAX,
+            ..self.limits
+        };
+        let mut lexer = LexerIter::new(chars).with_limits(limits);
+        let mut lexed = Position::default();
+        while let Some(t) = lexer.next() {
+            let end = lexer.current_position();
+            let t = match t {
+                Ok(t) => t,
+                Err(e) => {
+                    // More input can't bring a token back within the limits
+                    let beyond_limits = matches!(
+                        e,
+                        TokenizeError::StringTooLong
+                            | TokenizeError::TooManyIntDigits
+                            | TokenizeError::TooManyFracDigits
+                            | TokenizeError::TooManyExpDigits
+                    );
+                    let waiting = !last
+                        && !beyond_limits
+                        && (end.offset == len || e == TokenizeError::InvalidUTF8(Utf8Error::Truncated));
+                    if waiting {
+                        break;
+                    }
+                    self.position = self.position.after(end);
+                    self.status = Some(Err(e.clone()));
+                    return Err(e);
+                }
+            };
+            // Numbers and white spaces only end when something else begins
+            let delimited = !matches!(t, Token::Number(_) | Token::WhiteSpace);
+            if last || delimited || end.offset < len {
+                if self.tokens == self.limits.tokens {
+                    let e = TokenizeError::TooManyTokens;
+                    self.position = self.position.after(lexed);
+                    self.status = Some(Err(e.clone()));
+                    return Err(e);
+                }
+                self.tokens += 1;
+                tokens.push(t);
+                lexed = end;
+            } else {
+                break;
+            }
+        }
+        self.pending.drain(..lexed.offset);
+        self.position = self.position.after(lexed);
+        // The first pending byte begins the token
+        self.scan = Scan { scanned: 1, escape: false };
+        self.may_end();
+        if self.pending.is_empty() {
+            Ok(Status::Complete)
+        } else {
+            Ok(Status::NeedMoreInput)
+        }
+    }
+}
+
+/// How far the pending bytes were scanned for the end of their token.
+#[derive(Debug, Clone, Copy, Default)]
+struct Scan {
+    /// Number of pending bytes scanned
+    scanned: usize,
+    /// Whether the last byte scanned begins an escape sequence
+    escape: bool,
+}
+
+fn is_white_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+impl Default for PushLexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An automaton to which the input is pushed chunk by chunk (see [`PushLexer`]).
+pub struct PushParser {
+    lexer: PushLexer,
+    machine: Machine,
+    tokens: Vec<Token<'static>>,
+    error: Option<TokenizeError>,
+}
+
+impl PushParser {
+    /// Create a new one
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            lexer: PushLexer::new(),
+            machine: Machine::new(),
+            tokens: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Fails once the input goes beyond `limits` (see [`PushLexer::with_limits`]).
+    #[must_use]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.lexer = self.lexer.with_limits(limits);
+        self
+    }
+
+    /// Reads a new `chunk` of the input, and pushes the actions it completed to `actions`.
+    ///
+    /// The status is [`Status::Complete`] once a whole JSON value was read.
+    ///
+    /// # Errors
+    /// Returns the error found in the input (again and again once it was found).
+    pub fn feed(&mut self, chunk: &[u8], actions: &mut Vec<Action<'static>>) -> Result<Status, TokenizeError> {
+        self.check()?;
+        let lexed = self.lexer.feed(chunk, &mut self.tokens);
+        self.run(actions)?;
+        match lexed {
+            Ok(_) if self.machine.is_complete() && self.lexer.only_white_space_pending() => {
+                Ok(Status::Complete)
+            }
+            Ok(_) => Ok(Status::NeedMoreInput),
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+
+    /// Signals the end of the input, and pushes the last actions to `actions`
+    /// (the last one being [`Action::TheEnd`]).
+    ///
+    /// # Errors
+    /// Returns the error found in the input, for example if the document isn't complete.
+    pub fn finish(&mut self, actions: &mut Vec<Action<'static>>) -> Result<(), TokenizeError> {
+        self.check()?;
+        let lexed = self.lexer.finish(&mut self.tokens);
+        self.run(actions)?;
+        if let Err(e) = lexed {
+            return Err(self.fail(e));
+        }
+        match self.machine.end() {
+            Some(Ok(a)) => {
+                actions.push(a);
+                Ok(())
+            }
+            Some(Err(e)) => Err(self.fail(e)),
+            None => Err(self.fail(internal_error!())),
+        }
+    }
+
+    /// Returns the position of the first byte not lexed yet (or where the lexical error occured).
+    #[must_use]
+    pub fn current_position(&self) -> Position {
+        self.lexer.current_position()
+    }
+
+    fn check(&self) -> Result<(), TokenizeError> {
+        match &self.error {
+            None => Ok(()),
+            Some(e) => Err(e.clone()),
+        }
+    }
+
+    fn fail(&mut self, e: TokenizeError) -> TokenizeError {
+        self.error = Some(e.clone());
+        e
+    }
+
+    /// Gives the lexed tokens to the automaton.
+    fn run(&mut self, actions: &mut Vec<Action<'static>>) -> Result<(), TokenizeError> {
+        let mut tokens = std::mem::take(&mut self.tokens);
+        for t in tokens.drain(..) {
+            match self.machine.step(t) {
+                Some(Ok(a)) => actions.push(a),
+                Some(Err(e)) => return Err(self.fail(e)),
+                None => return Err(self.fail(internal_error!())),
+            }
+        }
+        self.tokens = tokens;
+        Ok(())
+    }
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::Automaton;
+
+    const DOCUMENTS: [&str; 12] = [
+        r#"{"a": [1, -2.5e+3, true, false, null], "bé\"\\": {"c": "d𝄞𝄞"}}"#,
+        "  123456789  ",
+        "0.125e-10",
+        r#""""#,
+        "[]",
+        "\n\t[ [ ] , { } ]\r\n",
+        "é",
+        "[1, 2",
+        r#"["abc\u00"]"#,
+        "[tru]",
+        "[1] 2",
+        "{\"a\"  1}",
+    ];
+
+    /// Lexes `input` by chunks of `size` bytes.
+    fn push_lex(input: &[u8], size: usize) -> (Vec<Token<'static>>, Result<(), TokenizeError>) {
+        let mut lexer = PushLexer::new();
+        let mut tokens = Vec::new();
+        for chunk in input.chunks(size) {
+            if let Err(e) = lexer.feed(chunk, &mut tokens) {
+                return (tokens, Err(e));
+            }
+        }
+        let r = lexer.finish(&mut tokens);
+        (tokens, r)
+    }
+
+    /// Parses `input` by chunks of `size` bytes.
+    fn push_parse(input: &[u8], size: usize) -> (Vec<Action<'static>>, Result<(), TokenizeError>) {
+        let mut parser = PushParser::new();
+        let mut actions = Vec::new();
+        for chunk in input.chunks(size) {
+            if let Err(e) = parser.feed(chunk, &mut actions) {
+                return (actions, Err(e));
+            }
+        }
+        let r = parser.finish(&mut actions);
+        (actions, r)
+    }
+
+    #[test]
+    fn same_tokens_as_lexer_iter() {
+        for document in DOCUMENTS {
+            let mut expected = Vec::new();
+            let mut report = Ok(());
+            for t in LexerIter::new(Chars::from(document)) {
+                match t {
+                    Ok(t) => expected.push(t),
+                    Err(e) => report = Err(e),
+                }
+            }
+            for size in 1..=document.len() {
+                let (tokens, r) = push_lex(document.as_bytes(), size);
+                assert_eq!(expected, tokens, "{} by {}", document, size);
+                assert_eq!(report, r, "{} by {}", document, size);
+            }
+        }
+    }
+
+    #[test]
+    fn same_actions_as_automaton() {
+        for document in DOCUMENTS {
+            let mut expected = Vec::new();
+            let mut end = Ok(());
+            for a in Automaton::new(Box::new(LexerIter::new(Chars::from(document)))) {
+                match a {
+                    Ok(a) => expected.push(a),
+                    Err(e) => {
+                        end = Err(e);
+                        break;
+                    }
+                }
+            }
+            for size in 1..=document.len() {
+                let (actions, r) = push_parse(document.as_bytes(), size);
+                assert_eq!(expected, actions, "{} by {}", document, size);
+                assert_eq!(end, r, "{} by {}", document, size);
+            }
+        }
+    }
+
+    #[test]
+    fn status() {
+        let mut parser = PushParser::new();
+        let mut actions = Vec::new();
+        assert_eq!(Ok(Status::NeedMoreInput), parser.feed(b"{\"a\": \"\xC3", &mut actions));
+        assert_eq!(
+            vec![Action::NewObject, Action::NewKey("a".into()), Action::Nothing, Action::Nothing],
+            actions
+        );
+        assert_eq!(Ok(Status::NeedMoreInput), parser.feed(b"\xA9\"", &mut actions));
+        assert_eq!(Ok(Status::Complete), parser.feed(b"} ", &mut actions));
+        assert_eq!(Ok(()), parser.finish(&mut actions));
+        assert_eq!(Some(&Action::TheEnd), actions.last());
+    }
+
+    #[test]
+    fn errors_are_sticky() {
+        let mut lexer = PushLexer::new();
+        let mut tokens = Vec::new();
+        assert_eq!(Ok(Status::NeedMoreInput), lexer.feed(b"[\n", &mut tokens));
+        assert_eq!(Err(TokenizeError::UnkownToken), lexer.feed(b" x ", &mut tokens));
+        let p = lexer.current_position();
+        assert_eq!((3, 2, 2), (p.offset, p.line, p.column));
+        assert_eq!(Err(TokenizeError::UnkownToken), lexer.feed(b"1", &mut tokens));
+        assert_eq!(Err(TokenizeError::UnkownToken), lexer.finish(&mut tokens));
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let (_, r) = push_lex(b"\"a\xE0\x80\"", 1);
+        assert_eq!(Err(TokenizeError::InvalidUTF8(Utf8Error::Overlong)), r);
+        let (_, r) = push_lex(b"\"a\xE2\x82", 1);
+        assert_eq!(Err(TokenizeError::InvalidUTF8(Utf8Error::Truncated)), r);
+    }
+
+    #[test]
+    fn limits() {
+        let limits = Limits {
+            string_length: 3,
+            int_digits: 2,
+            frac_digits: 2,
+            exp_digits: 2,
+            tokens: 6,
+        };
+        for document in ["[\"abc\", 12]", "[\"abcd\"]", "[123]", "[0.123]", "[1e123]", "[1, 2, 3]"] {
+            let expected: Result<Vec<Token>, TokenizeError> =
+                LexerIter::new(Chars::from(document)).with_limits(limits).collect();
+            for size in 1..=document.len() {
+                let mut lexer = PushLexer::new().with_limits(limits);
+                let mut tokens = Vec::new();
+                let r = document
+                    .as_bytes()
+                    .chunks(size)
+                    .try_for_each(|chunk| lexer.feed(chunk, &mut tokens).map(|_| ()))
+                    .and_then(|_| lexer.finish(&mut tokens));
+                assert_eq!(expected, r.map(|_| tokens), "{} by {}", document, size);
+            }
+        }
+    }
+
+    #[test]
+    fn long_tokens() {
+        let string = format!("[\"{}\\n{}\"]", "é".repeat(1 << 20), "a\\\"".repeat(1 << 20));
+        let number = format!("[-{}.{}e+{}]", "1".repeat(2 << 20), "2".repeat(1 << 20), "3".repeat(1 << 20));
+        let spaces = format!("[{}1]", " \n".repeat(2 << 20));
+        for document in [string, number, spaces] {
+            let mut lexer = PushLexer::new();
+            let mut tokens = Vec::new();
+            let r = document
+                .as_bytes()
+                .chunks(1000)
+                .try_for_each(|chunk| lexer.feed(chunk, &mut tokens).map(|_| ()))
+                .and_then(|_| lexer.finish(&mut tokens));
+            assert_eq!(Ok(()), r);
+            assert!(lexer.examined <= 3 * document.len(), "{} for {}", lexer.examined, document.len());
+            let expected: Vec<Token> = LexerIter::new(Chars::from(document.as_str())).map(|t| t.unwrap()).collect();
+            assert!(expected == tokens);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! Scanning of the runs of bytes of an input several at a time: 8 with `u64` bit tricks, or 16
+//! and 32 with SSE2 and AVX2 when the processor has them.
+//!
+//! Each scan has a scalar version, which the others must agree with.
+
+/// `0x01` in each byte.
+const ONES: u64 = u64::MAX / 0xFF;
+/// `0x7F` in each byte.
+const LOW_BITS: u64 = ONES * 0x7F;
+/// `0x80` in each byte.
+const HIGH_BITS: u64 = ONES * 0x80;
+
+/// Returns the length of the run of bytes at the start of `bytes` which are copied as they are
+/// into a JSON string: all but `"`, `\` and the control characters.
+pub(crate) fn string_run_len(bytes: &[u8]) -> usize {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if bytes.len() >= 32 && is_x86_feature_detected!("avx2") {
+            // SAFETY: the processor has AVX2
+            return unsafe { x86::string_run_len_avx2(bytes) };
+        }
+        if bytes.len() >= 16 && is_x86_feature_detected!("sse2") {
+            // SAFETY: the processor has SSE2
+            return unsafe { x86::string_run_len_sse2(bytes) };
+        }
+    }
+    string_run_len_swar(bytes)
+}
+
+/// Returns the length of the run of RFC 8259 JSON white space at the start of `bytes`.
+pub(crate) fn white_space_len(bytes: &[u8]) -> usize {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if bytes.len() >= 32 && is_x86_feature_detected!("avx2") {
+            // SAFETY: the processor has AVX2
+            return unsafe { x86::white_space_len_avx2(bytes) };
+        }
+        if bytes.len() >= 16 && is_x86_feature_detected!("sse2") {
+            // SAFETY: the processor has SSE2
+            return unsafe { x86::white_space_len_sse2(bytes) };
+        }
+    }
+    white_space_len_swar(bytes)
+}
+
+fn is_string_byte(b: u8) -> bool {
+    !matches!(b, b'"' | b'\\' | 0x00..=0x1F)
+}
+
+fn is_white_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+fn string_run_len_scalar(bytes: &[u8]) -> usize {
+    bytes.iter().position(|&b| !is_string_byte(b)).unwrap_or(bytes.len())
+}
+
+fn white_space_len_scalar(bytes: &[u8]) -> usize {
+    bytes.iter().position(|&b| !is_white_space(b)).unwrap_or(bytes.len())
+}
+
+/// Returns `b` in each byte.
+fn splat(b: u8) -> u64 {
+    ONES * u64::from(b)
+}
+
+/// Sets the high bit of exactly the bytes of `x` which are zero.
+fn zero_bytes(x: u64) -> u64 {
+    !(((x & LOW_BITS) + LOW_BITS) | x | LOW_BITS)
+}
+
+/// Reads the 8 bytes of `chunk` as a `u64`, the first one being the least significant.
+fn word(chunk: &[u8]) -> u64 {
+    let mut w = [0; 8];
+    w.copy_from_slice(chunk);
+    u64::from_le_bytes(w)
+}
+
+/// Returns the index of the first byte whose high bit is set in `mask`.
+fn first_byte(mask: u64) -> usize {
+    (mask.trailing_zeros() / 8) as usize
+}
+
+fn string_run_len_swar(bytes: &[u8]) -> usize {
+    let mut chunks = bytes.chunks_exact(8);
+    let mut len = 0;
+    for chunk in chunks.by_ref() {
+        let w = word(chunk);
+        let stops = zero_bytes(w ^ splat(b'"')) | zero_bytes(w ^ splat(b'\\')) | zero_bytes(w & splat(0xE0));
+        if stops != 0 {
+            return len + first_byte(stops);
+        }
+        len += 8;
+    }
+    len + string_run_len_scalar(chunks.remainder())
+}
+
+fn white_space_len_swar(bytes: &[u8]) -> usize {
+    let mut chunks = bytes.chunks_exact(8);
+    let mut len = 0;
+    for chunk in chunks.by_ref() {
+        let w = word(chunk);
+        let spaces = zero_bytes(w ^ splat(b' '))
+            | zero_bytes(w ^ splat(b'\t'))
+            | zero_bytes(w ^ splat(b'\n'))
+            | zero_bytes(w ^ splat(b'\r'));
+        let others = !spaces & HIGH_BITS;
+        if others != 0 {
+            return len + first_byte(others);
+        }
+        len += 8;
+    }
+    len + white_space_len_scalar(chunks.remainder())
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    /// # Safety
+    /// The processor must have SSE2.
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn string_run_len_sse2(bytes: &[u8]) -> usize {
+        let quote = _mm_set1_epi8(b'"' as i8);
+        let backslash = _mm_set1_epi8(b'\\' as i8);
+        let control = _mm_set1_epi8(0x1F);
+        let mut chunks = bytes.chunks_exact(16);
+        let mut len = 0;
+        for chunk in chunks.by_ref() {
+            let x = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+            // The unsigned maximum with 0x1F is 0x1F for the control characters only
+            let stops = _mm_or_si128(
+                _mm_or_si128(_mm_cmpeq_epi8(x, quote), _mm_cmpeq_epi8(x, backslash)),
+                _mm_cmpeq_epi8(_mm_max_epu8(x, control), control),
+            );
+            let mask = _mm_movemask_epi8(stops) as u32;
+            if mask != 0 {
+                return len + mask.trailing_zeros() as usize;
+            }
+            len += 16;
+        }
+        len + super::string_run_len_swar(chunks.remainder())
+    }
+
+    /// # Safety
+    /// The processor must have AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn string_run_len_avx2(bytes: &[u8]) -> usize {
+        let quote = _mm256_set1_epi8(b'"' as i8);
+        let backslash = _mm256_set1_epi8(b'\\' as i8);
+        let control = _mm256_set1_epi8(0x1F);
+        let mut chunks = bytes.chunks_exact(32);
+        let mut len = 0;
+        for chunk in chunks.by_ref() {
+            let x = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+            let stops = _mm256_or_si256(
+                _mm256_or_si256(_mm256_cmpeq_epi8(x, quote), _mm256_cmpeq_epi8(x, backslash)),
+                _mm256_cmpeq_epi8(_mm256_max_epu8(x, control), control),
+            );
+            let mask = _mm256_movemask_epi8(stops) as u32;
+            if mask != 0 {
+                return len + mask.trailing_zeros() as usize;
+            }
+            len += 32;
+        }
+        len + string_run_len_sse2(chunks.remainder())
+    }
+
+    /// # Safety
+    /// The processor must have SSE2.
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn white_space_len_sse2(bytes: &[u8]) -> usize {
+        let mut chunks = bytes.chunks_exact(16);
+        let mut len = 0;
+        for chunk in chunks.by_ref() {
+            let x = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+            let spaces = _mm_or_si128(
+                _mm_or_si128(
+                    _mm_cmpeq_epi8(x, _mm_set1_epi8(b' ' as i8)),
+                    _mm_cmpeq_epi8(x, _mm_set1_epi8(b'\t' as i8)),
+                ),
+                _mm_or_si128(
+                    _mm_cmpeq_epi8(x, _mm_set1_epi8(b'\n' as i8)),
+                    _mm_cmpeq_epi8(x, _mm_set1_epi8(b'\r' as i8)),
+                ),
+            );
+            let others = !(_mm_movemask_epi8(spaces) as u32) & 0xFFFF;
+            if others != 0 {
+                return len + others.trailing_zeros() as usize;
+            }
+            len += 16;
+        }
+        len + super::white_space_len_swar(chunks.remainder())
+    }
+
+    /// # Safety
+    /// The processor must have AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn white_space_len_avx2(bytes: &[u8]) -> usize {
+        let mut chunks = bytes.chunks_exact(32);
+        let mut len = 0;
+        for chunk in chunks.by_ref() {
+            let x = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+            let spaces = _mm256_or_si256(
+                _mm256_or_si256(
+                    _mm256_cmpeq_epi8(x, _mm256_set1_epi8(b' ' as i8)),
+                    _mm256_cmpeq_epi8(x, _mm256_set1_epi8(b'\t' as i8)),
+                ),
+                _mm256_or_si256(
+                    _mm256_cmpeq_epi8(x, _mm256_set1_epi8(b'\n' as i8)),
+                    _mm256_cmpeq_epi8(x, _mm256_set1_epi8(b'\r' as i8)),
+                ),
+            );
+            let others = !(_mm256_movemask_epi8(spaces) as u32);
+            if others != 0 {
+                return len + others.trailing_zeros() as usize;
+            }
+            len += 32;
+        }
+        len + white_space_len_sse2(chunks.remainder())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A xorshift generator of bytes, most of them among the ones the scans stop on or
+    /// skip.
+    struct Bytes(u32);
+
+    impl Bytes {
+        fn next(&mut self) -> u8 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            const INTERESTING: &[u8] = b" \t\n\r\"\\\x00\x1F\x20\x21\x5B\x5D\x7F\x80\xC3\xA9\xFF";
+            let r = self.0 >> 8;
+            if r & 3 == 0 {
+                (r >> 2) as u8
+            } else {
+                INTERESTING[(r >> 2) as usize % INTERESTING.len()]
+            }
+        }
+
+        /// A run of `len` bytes which the scan accepts, then anything.
+        fn run(&mut self, len: usize, accepted: fn(u8) -> bool) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            while bytes.len() < len {
+                let b = self.next();
+                if accepted(b) {
+                    bytes.push(b);
+                }
+            }
+            bytes.extend((0..self.next() % 8).map(|_| self.next()));
+            bytes
+        }
+    }
+
+    /// Checks that every version of a scan agrees with the scalar one.
+    fn check(scalar: fn(&[u8]) -> usize, others: &[fn(&[u8]) -> usize], accepted: fn(u8) -> bool) {
+        let mut bytes = Bytes(0x2545_F491);
+        for len in 0..200 {
+            for _ in 0..20 {
+                let input = bytes.run(len, accepted);
+                for start in 0..input.len().min(40) {
+                    let input = &input[start..];
+                    let expected = scalar(input);
+                    for scan in others {
+                        assert_eq!(expected, scan(input), "{:?}", input);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn string_runs() {
+        let mut scans: Vec<fn(&[u8]) -> usize> = vec![string_run_len_swar, string_run_len];
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("sse2") {
+                // SAFETY: the processor has SSE2
+                scans.push(|b| unsafe { x86::string_run_len_sse2(b) });
+            }
+            if is_x86_feature_detected!("avx2") {
+                // SAFETY: the processor has AVX2
+                scans.push(|b| unsafe { x86::string_run_len_avx2(b) });
+            }
+        }
+        check(string_run_len_scalar, &scans, is_string_byte);
+    }
+
+    #[test]
+    fn white_spaces() {
+        let mut scans: Vec<fn(&[u8]) -> usize> = vec![white_space_len_swar, white_space_len];
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("sse2") {
+                // SAFETY: the processor has SSE2
+                scans.push(|b| unsafe { x86::white_space_len_sse2(b) });
+            }
+            if is_x86_feature_detected!("avx2") {
+                // SAFETY: the processor has AVX2
+                scans.push(|b| unsafe { x86::white_space_len_avx2(b) });
+            }
+        }
+        check(white_space_len_scalar, &scans, is_white_space);
+    }
+}
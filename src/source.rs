@@ -0,0 +1,399 @@
+//! The inputs a [`crate::lexer::SourceLexer`] can read.
+
+use std::borrow::Cow;
+
+use crate::{
+    chars::{utf8_error, BomPolicy, ByteSource, Chars, Encoding, Position},
+    err::TokenizeError,
+    scan,
+};
+
+/// An input read char by char, with one char of lookahead.
+///
+/// `'a` is the lifetime of the strings which can be borrowed from the input.
+pub trait Source<'a> {
+    /// Returns the next char without consuming it, `None` at the end of the input (or when it
+    /// can't be decoded).
+    fn peek_char(&mut self) -> Option<char>;
+
+    /// Consumes the next char.
+    fn next_char(&mut self) -> Option<char>;
+
+    /// Returns the position of the next char.
+    fn current_position(&self) -> Position;
+
+    /// Returns the error which ended the input early (while decoding it), if any.
+    fn error(&self) -> Option<&TokenizeError>;
+
+    /// Consumes the chars which are copied as they are into a JSON string (up to a quotation
+    /// mark, a reverse solidus or a control character), and appends them to `s`.
+    ///
+    /// It stops before `s` gets longer than `limit` bytes.
+    fn read_string_chars(&mut self, s: &mut String, limit: usize) {
+        while let Some(c) = self.peek_char() {
+            if matches!(c, '"' | '\\' | '\u{0000}'..='\u{001F}') || s.len() + c.len_utf8() > limit {
+                break;
+            }
+            self.next_char();
+            s.push(c);
+        }
+    }
+
+    /// Consumes the RFC 8259 JSON white space.
+    fn skip_white_space(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek_char() {
+            self.next_char();
+        }
+    }
+
+    /// Like [`Source::read_string_chars`], but returns the chars borrowed from the input (at most
+    /// `limit` bytes).
+    ///
+    /// Returns `None` (without consuming anything) when the input can't lend them.
+    fn borrow_string_chars(&mut self, _limit: usize) -> Option<&'a str> {
+        None
+    }
+
+    /// Returns the text of the input from the byte offset `start` to `end`, if the input can lend
+    /// it.
+    fn text(&self, _start: usize, _end: usize) -> Option<&'a str> {
+        None
+    }
+}
+
+impl<S: ByteSource> Source<'_> for Chars<S> {
+    fn peek_char(&mut self) -> Option<char> {
+        self.peek().copied()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        self.next()
+    }
+
+    fn current_position(&self) -> Position {
+        Chars::current_position(self)
+    }
+
+    fn error(&self) -> Option<&TokenizeError> {
+        match &self.status {
+            Some(Err(e)) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A borrowed slice of chars.
+///
+/// The strings are copied, a `str` can't be borrowed from chars.
+pub struct CharSlice<'a> {
+    input: &'a [char],
+    pos: usize,
+    position: Position,
+}
+
+impl<'a> CharSlice<'a> {
+    /// Create a new one
+    pub fn new(input: &'a [char]) -> Self {
+        Self {
+            input,
+            pos: 0,
+            position: Position::default(),
+        }
+    }
+}
+
+impl<'a> From<&'a [char]> for CharSlice<'a> {
+    fn from(input: &'a [char]) -> Self {
+        Self::new(input)
+    }
+}
+
+impl Source<'_> for CharSlice<'_> {
+    #[inline]
+    fn peek_char(&mut self) -> Option<char> {
+        self.input.get(self.pos).copied()
+    }
+
+    #[inline]
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.pos += 1;
+        self.position.advance(c, c.len_utf8());
+        Some(c)
+    }
+
+    fn current_position(&self) -> Position {
+        self.position
+    }
+
+    fn error(&self) -> Option<&TokenizeError> {
+        None
+    }
+}
+
+/// A borrowed slice of UTF-8 bytes, decoded in place.
+///
+/// An input given as a `&str` isn't validated again. The strings without escape sequences are
+/// borrowed from the input, the runs of bytes of the others are copied at once.
+pub struct ByteSlice<'a> {
+    input: &'a [u8],
+    /// The input, when it is already known to be valid UTF-8
+    text: Option<&'a str>,
+    position: Position,
+    error: Option<TokenizeError>,
+    /// Whether a byte order mark was skipped, with [`BomPolicy::Report`]
+    bom: bool,
+}
+
+impl<'a> ByteSlice<'a> {
+    /// Create a new one, checking that `input` is valid UTF-8 while reading it.
+    ///
+    /// Like [`Chars`], it rejects a leading byte order mark by default.
+    pub fn new(input: &'a [u8]) -> Self {
+        let mut slice = Self {
+            input,
+            text: None,
+            position: Position::default(),
+            error: None,
+            bom: false,
+        };
+        slice.set_bom_policy(BomPolicy::Reject);
+        slice
+    }
+
+    /// Sets what to do with a leading byte order mark, before anything is read.
+    #[must_use]
+    pub fn with_bom_policy(mut self, bom_policy: BomPolicy) -> Self {
+        self.set_bom_policy(bom_policy);
+        self
+    }
+
+    /// See [`ByteSlice::with_bom_policy`].
+    pub(crate) fn set_bom_policy(&mut self, bom_policy: BomPolicy) {
+        let bom = Encoding::Utf8.bom();
+        if !self.input.starts_with(bom) {
+            return;
+        }
+        self.position = Position::default();
+        self.error = None;
+        self.bom = false;
+        match bom_policy {
+            BomPolicy::Skip => self.position.offset = bom.len(),
+            BomPolicy::Report => {
+                self.position.offset = bom.len();
+                self.bom = true;
+            }
+            BomPolicy::Reject => self.error = Some(TokenizeError::ByteOrderMark),
+        }
+    }
+
+    /// Whether a byte order mark was skipped at the beginning of the input.
+    ///
+    /// It is only remembered with [`BomPolicy::Report`].
+    #[must_use]
+    pub fn had_bom(&self) -> bool {
+        self.bom
+    }
+}
+
+impl<'a> From<&'a [u8]> for ByteSlice<'a> {
+    fn from(input: &'a [u8]) -> Self {
+        Self::new(input)
+    }
+}
+
+impl<'a> From<&'a str> for ByteSlice<'a> {
+    fn from(input: &'a str) -> Self {
+        Self {
+            text: Some(input),
+            ..Self::new(input.as_bytes())
+        }
+    }
+}
+
+impl<'a> ByteSlice<'a> {
+    /// Consumes the chars which are copied as they are into a JSON string, at most `limit`
+    /// bytes, and returns them.
+    fn string_run(&mut self, limit: usize) -> &'a str {
+        if self.error.is_some() {
+            return "";
+        }
+        let start = self.position.offset;
+        let max = self.input.len().min(start.saturating_add(limit));
+        let mut end = start + scan::string_run_len(&self.input[start..max]);
+        // The run ends on an ASCII byte (or at the end) unless it is cut by `limit`, which is
+        // moved back to a char boundary; an invalid sequence is left for `peek_char` to report
+        let run = match self.text {
+            Some(text) => {
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                text.get(start..end).unwrap_or_default()
+            }
+            None => match std::str::from_utf8(&self.input[start..end]) {
+                Ok(run) => run,
+                Err(e) => std::str::from_utf8(&self.input[start..start + e.valid_up_to()]).unwrap_or_default(),
+            },
+        };
+        // A run has no line feed
+        self.position.offset += run.len();
+        self.position.column += run.chars().count();
+        run
+    }
+}
+
+impl<'a> Source<'a> for ByteSlice<'a> {
+    #[inline]
+    fn peek_char(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        let pos = self.position.offset;
+        let b = *self.input.get(pos)?;
+        if b < 0x80 {
+            return Some(char::from(b));
+        }
+        let rest = &self.input[pos..];
+        let decoded = match self.text {
+            Some(text) => text.get(pos..).and_then(|t| t.chars().next()),
+            None => match std::str::from_utf8(&rest[..rest.len().min(4)]) {
+                Ok(s) => s.chars().next(),
+                Err(e) => std::str::from_utf8(&rest[..e.valid_up_to()])
+                    .ok()
+                    .and_then(|s| s.chars().next()),
+            },
+        };
+        if decoded.is_none() {
+            self.error = utf8_error(rest);
+        }
+        decoded
+    }
+
+    #[inline]
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.position.advance(c, c.len_utf8());
+        Some(c)
+    }
+
+    fn current_position(&self) -> Position {
+        self.position
+    }
+
+    fn error(&self) -> Option<&TokenizeError> {
+        self.error.as_ref()
+    }
+
+    fn read_string_chars(&mut self, s: &mut String, limit: usize) {
+        s.push_str(self.string_run(limit.saturating_sub(s.len())));
+    }
+
+    fn skip_white_space(&mut self) {
+        if self.error.is_some() {
+            return;
+        }
+        let run = &self.input[self.position.offset..];
+        let run = &run[..scan::white_space_len(run)];
+        // The white space is ASCII: one byte per char
+        self.position.offset += run.len();
+        match run.iter().rposition(|&b| b == b'\n') {
+            Some(last) => {
+                self.position.line += run.iter().filter(|&&b| b == b'\n').count();
+                self.position.column = run.len() - last;
+            }
+            None => self.position.column += run.len(),
+        }
+    }
+
+    fn borrow_string_chars(&mut self, limit: usize) -> Option<&'a str> {
+        Some(self.string_run(limit))
+    }
+
+    fn text(&self, start: usize, end: usize) -> Option<&'a str> {
+        match self.text {
+            Some(text) => text.get(start..end),
+            None => std::str::from_utf8(self.input.get(start..end)?).ok(),
+        }
+    }
+}
+
+/// A source keeping a copy of the chars read from another one, which can't lend its text.
+pub(crate) struct Recorder<Src> {
+    inner: Src,
+    /// Whether the chars are copied to `record`
+    recording: bool,
+    record: String,
+}
+
+impl<'a, Src: Source<'a>> Recorder<Src> {
+    pub(crate) fn new(inner: Src) -> Self {
+        Self {
+            recording: inner.text(0, 0).is_none(),
+            inner,
+            record: String::new(),
+        }
+    }
+
+    /// Returns the text read from `start` to the current position, and empties the record.
+    pub(crate) fn take_text(&mut self, start: Position) -> Cow<'a, str> {
+        if self.recording {
+            Cow::Owned(std::mem::take(&mut self.record))
+        } else {
+            let end = self.inner.current_position().offset;
+            Cow::Borrowed(self.inner.text(start.offset, end).unwrap_or_default())
+        }
+    }
+}
+
+impl<'a, Src: Source<'a>> Source<'a> for Recorder<Src> {
+    fn peek_char(&mut self) -> Option<char> {
+        self.inner.peek_char()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.inner.next_char()?;
+        if self.recording {
+            self.record.push(c);
+        }
+        Some(c)
+    }
+
+    fn current_position(&self) -> Position {
+        self.inner.current_position()
+    }
+
+    fn error(&self) -> Option<&TokenizeError> {
+        self.inner.error()
+    }
+
+    fn skip_white_space(&mut self) {
+        if self.recording {
+            while let Some(c @ (' ' | '\t' | '\n' | '\r')) = self.inner.peek_char() {
+                self.inner.next_char();
+                self.record.push(c);
+            }
+        } else {
+            self.inner.skip_white_space();
+        }
+    }
+
+    fn read_string_chars(&mut self, s: &mut String, limit: usize) {
+        let len = s.len();
+        self.inner.read_string_chars(s, limit);
+        if self.recording {
+            self.record.push_str(&s[len..]);
+        }
+    }
+
+    fn borrow_string_chars(&mut self, limit: usize) -> Option<&'a str> {
+        let run = self.inner.borrow_string_chars(limit)?;
+        if self.recording {
+            self.record.push_str(run);
+        }
+        Some(run)
+    }
+
+    fn text(&self, start: usize, end: usize) -> Option<&'a str> {
+        self.inner.text(start, end)
+    }
+}
@@ -1,10 +1,14 @@
 //! Module for the representation of the tokens.
 
-use crate::number::Number;
+use std::borrow::Cow;
+
+use crate::{chars::Position, number::Number};
 /// Representation of the RFC8259 JSON tokens.
+///
+/// The strings borrow from the input when the lexer can (see [`crate::source::Source::borrow_string_chars`]).
 #[derive(PartialEq, Eq, Debug, Clone)]
 #[allow(missing_docs)]
-pub enum Token {
+pub enum Token<'a> {
     ArrayBegin,
     ArrayEnd,
     ObjectBegin,
@@ -12,9 +16,175 @@ pub enum Token {
     NameSeparator,
     ValueSeparator,
     Number(Number),
-    String(String),
+    String(JsonString<'a>),
     True,
     False,
     Null,
     WhiteSpace,
+    /// A JSON5 identifier (an unquoted key, or an unsigned `Infinity` or `NaN`)
+    Identifier(Cow<'a, str>),
+    /// A JSON5 number which isn't finite
+    NonFinite(NonFinite),
+    /// A comment, with its delimiters (only yielded when the lexer keeps them)
+    Comment(Cow<'a, str>),
+}
+
+impl Token<'_> {
+    /// Returns the same token, owning its string.
+    #[must_use]
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::ArrayBegin => Token::ArrayBegin,
+            Token::ArrayEnd => Token::ArrayEnd,
+            Token::ObjectBegin => Token::ObjectBegin,
+            Token::ObjectEnd => Token::ObjectEnd,
+            Token::NameSeparator => Token::NameSeparator,
+            Token::ValueSeparator => Token::ValueSeparator,
+            Token::Number(n) => Token::Number(n),
+            Token::String(s) => Token::String(s.into_owned()),
+            Token::True => Token::True,
+            Token::False => Token::False,
+            Token::Null => Token::Null,
+            Token::WhiteSpace => Token::WhiteSpace,
+            Token::Identifier(s) => Token::Identifier(Cow::Owned(s.into_owned())),
+            Token::NonFinite(n) => Token::NonFinite(n),
+            Token::Comment(s) => Token::Comment(Cow::Owned(s.into_owned())),
+        }
+    }
+}
+
+/// The text of a JSON string, telling how it was decoded.
+///
+/// Only the strings with lone surrogate escapes (like `"\uDC00"`) aren't [`JsonString::Text`]:
+/// how they are decoded depends on the [`crate::lexer::Surrogates`] policy of the lexer.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum JsonString<'a> {
+    /// Well-formed Unicode text, borrowed from the input when the lexer can
+    Text(Cow<'a, str>),
+    /// Text whose lone surrogates were replaced by U+FFFD
+    Replaced(String),
+    /// WTF-8 bytes, the lone surrogates being encoded like the other code points
+    Wtf8(Vec<u8>),
+    /// UTF-16 code units, the lone surrogates included
+    Utf16(Vec<u16>),
+}
+
+impl JsonString<'_> {
+    /// Returns the same string, owning its text.
+    #[must_use]
+    pub fn into_owned(self) -> JsonString<'static> {
+        match self {
+            JsonString::Text(s) => JsonString::Text(Cow::Owned(s.into_owned())),
+            JsonString::Replaced(s) => JsonString::Replaced(s),
+            JsonString::Wtf8(b) => JsonString::Wtf8(b),
+            JsonString::Utf16(u) => JsonString::Utf16(u),
+        }
+    }
+
+    /// Returns the text, unless it holds lone surrogates.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonString::Text(s) => Some(s),
+            JsonString::Replaced(s) => Some(s),
+            JsonString::Wtf8(_) | JsonString::Utf16(_) => None,
+        }
+    }
+
+    /// Returns the text, the lone surrogates being replaced by U+FFFD.
+    #[must_use]
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        match self {
+            JsonString::Text(s) => Cow::Borrowed(s),
+            JsonString::Replaced(s) => Cow::Borrowed(s),
+            JsonString::Wtf8(b) => Cow::Owned(String::from_utf16_lossy(&wtf8_to_utf16(b))),
+            JsonString::Utf16(u) => Cow::Owned(String::from_utf16_lossy(u)),
+        }
+    }
+}
+
+/// Decodes WTF-8 `bytes` (as made by the lexer) to UTF-16 code units.
+pub(crate) fn wtf8_to_utf16(bytes: &[u8]) -> Vec<u16> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let (len, mut code) = match bytes[i] {
+            b @ 0x00..=0x7F => (1, u32::from(b)),
+            b @ 0xC0..=0xDF => (2, u32::from(b & 0x1F)),
+            b @ 0xE0..=0xEF => (3, u32::from(b & 0x0F)),
+            b => (4, u32::from(b & 0x07)),
+        };
+        for b in bytes.iter().skip(i + 1).take(len - 1) {
+            code = code << 6 | u32::from(b & 0x3F);
+        }
+        i += len;
+        match char::from_u32(code) {
+            Some(c) => units.extend_from_slice(c.encode_utf16(&mut [0; 2])),
+            // A surrogate
+            None => units.push(code as u16),
+        }
+    }
+    units
+}
+
+impl<'a> From<&'a str> for JsonString<'a> {
+    fn from(s: &'a str) -> Self {
+        JsonString::Text(Cow::Borrowed(s))
+    }
+}
+
+impl From<String> for JsonString<'_> {
+    fn from(s: String) -> Self {
+        JsonString::Text(Cow::Owned(s))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for JsonString<'a> {
+    fn from(s: Cow<'a, str>) -> Self {
+        JsonString::Text(s)
+    }
+}
+
+/// The numbers of JSON5 which aren't finite.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[allow(missing_docs)]
+pub enum NonFinite {
+    Infinity,
+    NegInfinity,
+    NaN,
+}
+
+/// Where a token is in the input, from `start` (included) to `end` (excluded).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    /// Position of the first char of the token
+    pub start: Position,
+    /// Position of the char following the token
+    pub end: Position,
+}
+
+/// A token with its exact text in the input (see [`crate::lexer::Lossless`]).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LosslessToken<'a> {
+    /// The token
+    pub token: Token<'a>,
+    /// Its text in the input, quotes, escape sequences and white spaces included
+    pub text: Cow<'a, str>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lossy_strings() {
+        let text = JsonString::from("a\u{1D11E}");
+        assert_eq!(Some("a\u{1D11E}"), text.as_str());
+        assert_eq!("a\u{1D11E}", text.to_string_lossy());
+        let wtf8 = JsonString::Wtf8(b"a\xED\xA0\xB4\xF0\x9D\x84\x9E\xED\xB0\x80".to_vec());
+        assert_eq!(None, wtf8.as_str());
+        assert_eq!("a\u{FFFD}\u{1D11E}\u{FFFD}", wtf8.to_string_lossy());
+        let utf16 = JsonString::Utf16(vec![0x61, 0xD834, 0xD834, 0xDD1E]);
+        assert_eq!("a\u{FFFD}\u{1D11E}", utf16.to_string_lossy());
+    }
 }
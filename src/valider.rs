@@ -1,14 +1,19 @@
 //! A JSON validator
+//!
+//! Usage: `portevalider [--mmap] [--jsonc | --json5] FILE`, where `--mmap` maps the file in
+//! memory instead of reading it, and `--jsonc` or `--json5` accept these dialects of JSON.
 
 use libporte::automaton::{Action, Automaton};
 use libporte::chars::Chars;
+use libporte::lexer::{Dialect, Lexer};
 use libporte::lexer_iter::LexerIter;
+#[cfg(feature = "mmap")]
+use libporte::mmap::MappedFile;
 use libporte::{err::TokenizeError};
 
 use std::{
     env,
     fs::File,
-    io::{BufReader, Read},
     process::exit,
 };
 
@@ -21,20 +26,57 @@ const EXIT_INVALID: i32 = 1;
 /// Exit code returned when an internal error in the parser occured
 const EXIT_FAILURE: i32 = 2;
 
+/// Exit code returned when the input couldn't be read
+const EXIT_IO_ERROR: i32 = 3;
+
+/// Exit code returned when the arguments are wrong
+const EXIT_USAGE: i32 = 64;
+
 fn main() {
-    let s: Vec<String> = env::args().collect();
-    let s = &s[1];
-    let s = match File::open(s) {
-        Ok(f) => BufReader::new(f).bytes().map(|x| x.unwrap()),
-        Err(_) => todo!(),
-    };
-    
-    let s = Chars::new(Box::new(s));
-    let end = Automaton::new(Box::new(LexerIter::new(s))).last();
+    let mut mmap = false;
+    let mut dialect = Dialect::Json;
+    let mut path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--mmap" => mmap = true,
+            "--jsonc" => dialect = Dialect::Jsonc,
+            "--json5" => dialect = Dialect::Json5,
+            _ if path.is_none() => path = Some(arg),
+            _ => usage(),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+
+    if mmap {
+        #[cfg(feature = "mmap")]
+        match MappedFile::open(&path) {
+            Ok(f) => valid(Automaton::new(f.lexer().with_dialect(dialect))),
+            Err(e) => {eprintln!("{}: {}", path, e); exit(EXIT_IO_ERROR)},
+        }
+        #[cfg(not(feature = "mmap"))]
+        {eprintln!("portevalider was built without the mmap feature"); exit(EXIT_USAGE)}
+    } else {
+        match File::open(&path) {
+            Ok(f) => valid(Automaton::new(LexerIter::new(Chars::from_reader(f)).with_dialect(dialect))),
+            Err(e) => {eprintln!("{}: {}", path, e); exit(EXIT_IO_ERROR)},
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: portevalider [--mmap] [--jsonc | --json5] FILE");
+    exit(EXIT_USAGE)
+}
+
+/// Runs the automaton to the end, and exits with the matching code.
+fn valid<'a, L: Lexer<'a>>(mut automaton: Automaton<'a, L>) -> ! {
+    let end = automaton.by_ref().last();
 
     match end {
         Some(Ok(Action::TheEnd)) => {exit(EXIT_VALID)},
         Some(Err(TokenizeError::InternalError(e))) => {eprintln!("{:?}",e); exit(EXIT_FAILURE)},
+        Some(Err(TokenizeError::Io(e))) => {eprintln!("{}: {}", automaton.current_position(), e); exit(EXIT_IO_ERROR)},
+        Some(Err(e)) => {eprintln!("{}: {:?}", automaton.current_position(), e); exit(EXIT_INVALID)},
         Some(_) => {exit(EXIT_INVALID)},
         None => {exit(EXIT_INVALID)}
     };
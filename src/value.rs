@@ -1,11 +1,37 @@
-use crate::number::Number;
+//! Module for the representation of the JSON values.
+
+use crate::{number::Number, token::{JsonString, NonFinite}};
+/// A JSON value, as parsed by [`crate::ast::parse_and_valid`].
+///
+/// The strings keep how they were decoded (see [`crate::lexer::Surrogates`]):
+/// ```
+/// use libporte::{
+///     ast::parse_and_valid,
+///     lexer::{Dialect, Surrogates},
+///     lexer_bytes::LexerBytes,
+///     token::{JsonString, NonFinite},
+///     value::Value,
+/// };
+///
+/// let lexer = LexerBytes::from(r#"["\uD800", "a"]"#).with_surrogates(Surrogates::Wtf8);
+/// match parse_and_valid(lexer) {
+///     Ok(Value::Array(values)) => {
+///         assert_eq!(Value::String(JsonString::Wtf8(vec![0xED, 0xA0, 0x80])), values[0]);
+///         assert!(matches!(&values[1], Value::String(JsonString::Text(s)) if s == "a"));
+///     }
+///     r => unreachable!("{:?}", r),
+/// }
+/// let lexer = LexerBytes::from("NaN").with_dialect(Dialect::Json5);
+/// assert_eq!(Ok(Value::NonFinite(NonFinite::NaN)), parse_and_valid(lexer));
+/// ```
 #[allow(missing_docs)]
 #[derive(Debug, PartialEq, Eq)]
-pub enum Value {
-    Array(Vec<Value>),
-    Object(Vec<(String, Value)>),
+pub enum Value<'a> {
+    Array(Vec<Value<'a>>),
+    Object(Vec<(JsonString<'a>, Value<'a>)>),
     Number(Number),
-    String(String),
+    NonFinite(NonFinite),
+    String(JsonString<'a>),
     True,
     False,
     Null,
@@ -0,0 +1,203 @@
+//! Writing of a stream of tokens as JSON text.
+
+use std::io::{self, Write};
+
+use crate::token::{wtf8_to_utf16, JsonString, NonFinite, Token};
+
+/// Writes [`Token`]s to an [`io::Write`], to make a JSON text of a well-formed sequence of tokens.
+///
+/// The strings are escaped again. The numbers are written in the JSON decimal form, whatever the
+/// form they were read in: `4E+8` is written as `4e8`, and the JSON5 `0xFF` and `.5` as `255` and
+/// `0.5`. A JSON5 `-NaN` is lexed as [`NonFinite::NaN`], so it is written as `NaN`.
+/// A [`Token::WhiteSpace`] is written as a single space, and a line comment is followed by a line
+/// feed, so that the tokens stay apart.
+///
+/// The writer doesn't buffer: wrap the output in an [`io::BufWriter`] when it is costly to write to.
+#[derive(Debug)]
+pub struct TokenWriter<W> {
+    out: W,
+}
+
+impl<W: Write> TokenWriter<W> {
+    /// Creates a writer of tokens to `out`.
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// Writes a token.
+    ///
+    /// # Errors
+    /// Fails when the output does.
+    pub fn write_token(&mut self, token: &Token<'_>) -> io::Result<()> {
+        let out = &mut self.out;
+        match token {
+            Token::ArrayBegin => out.write_all(b"["),
+            Token::ArrayEnd => out.write_all(b"]"),
+            Token::ObjectBegin => out.write_all(b"{"),
+            Token::ObjectEnd => out.write_all(b"}"),
+            Token::NameSeparator => out.write_all(b":"),
+            Token::ValueSeparator => out.write_all(b","),
+            Token::Number(n) => write!(out, "{}", n),
+            Token::String(s) => write_string(out, s),
+            Token::True => out.write_all(b"true"),
+            Token::False => out.write_all(b"false"),
+            Token::Null => out.write_all(b"null"),
+            Token::WhiteSpace => out.write_all(b" "),
+            Token::Identifier(i) => out.write_all(i.as_bytes()),
+            Token::NonFinite(NonFinite::Infinity) => out.write_all(b"Infinity"),
+            Token::NonFinite(NonFinite::NegInfinity) => out.write_all(b"-Infinity"),
+            Token::NonFinite(NonFinite::NaN) => out.write_all(b"NaN"),
+            Token::Comment(c) => {
+                out.write_all(c.as_bytes())?;
+                if c.starts_with("//") {
+                    out.write_all(b"\n")?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes all the `tokens`.
+    ///
+    /// # Errors
+    /// Fails when the output does.
+    pub fn write_tokens<'a>(&mut self, tokens: impl IntoIterator<Item = Token<'a>>) -> io::Result<()> {
+        tokens.into_iter().try_for_each(|t| self.write_token(&t))
+    }
+
+    /// Flushes the output.
+    ///
+    /// # Errors
+    /// Fails when the output does.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Returns the output.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Writes `s` as a JSON string, escaping the quotes, the backslashes, the control characters and
+/// the lone surrogates.
+fn write_string<W: Write>(out: &mut W, s: &JsonString<'_>) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    match s {
+        JsonString::Text(s) => write_str(out, s)?,
+        JsonString::Replaced(s) => write_str(out, s)?,
+        JsonString::Wtf8(b) => write_utf16(out, &wtf8_to_utf16(b))?,
+        JsonString::Utf16(u) => write_utf16(out, u)?,
+    }
+    out.write_all(b"\"")
+}
+
+/// Writes the text of UTF-16 code units, escaped, with escape sequences for the lone surrogates.
+fn write_utf16<W: Write>(out: &mut W, units: &[u16]) -> io::Result<()> {
+    for c in char::decode_utf16(units.iter().copied()) {
+        match c {
+            Ok(c) => write_str(out, c.encode_utf8(&mut [0; 4]))?,
+            Err(e) => write!(out, "\\u{:04X}", e.unpaired_surrogate())?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes the text of a string, escaped.
+fn write_str<W: Write>(out: &mut W, s: &str) -> io::Result<()> {
+    let bytes = s.as_bytes();
+    // The start of the bytes which aren't written yet
+    let mut start = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        let escape: &[u8] = match b {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\x08' => b"\\b",
+            b'\x0C' => b"\\f",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x00..=0x1F => b"",
+            _ => continue,
+        };
+        out.write_all(&bytes[start..i])?;
+        if escape.is_empty() {
+            write!(out, "\\u{:04X}", b)?;
+        } else {
+            out.write_all(escape)?;
+        }
+        start = i + 1;
+    }
+    out.write_all(&bytes[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lexer::{Dialect, Surrogates},
+        lexer_bytes::LexerBytes,
+    };
+
+    /// Writes the tokens of `lexer`.
+    fn rewrite(lexer: LexerBytes) -> String {
+        let mut w = TokenWriter::new(Vec::new());
+        for t in lexer {
+            match t {
+                Ok(t) => w.write_token(&t).unwrap(),
+                Err(e) => unreachable!("{:?}", e),
+            }
+        }
+        String::from_utf8(w.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn documents() {
+        let rewrite = |input| rewrite(LexerBytes::from(input));
+        assert_eq!("[1,-2.50,3e-7,4e8,true,false,null]", rewrite("[1,-2.50,3e-7,4E+8,true,false,null]"));
+        assert_eq!(r#"{"a": [1, {}]}"#, rewrite(r#"{"a": [1, {}]}"#));
+        assert_eq!("[ ]", rewrite("[\n\t\r ]"));
+    }
+
+    #[test]
+    fn strings() {
+        let input = r#"["a\"b\\c\/d\b\f\n\r\t\u0001\u001F é€𝄞\uD834\uDD1E"]"#;
+        assert_eq!(r#"["a\"b\\c/d\b\f\n\r\t\u0001\u001F é€𝄞𝄞"]"#, rewrite(LexerBytes::from(input)));
+        let input = r#"["\uDC00a\uD834"]"#;
+        let rewrite = |surrogates| rewrite(LexerBytes::from(input).with_surrogates(surrogates));
+        assert_eq!(input, rewrite(Surrogates::Wtf8));
+        assert_eq!(input, rewrite(Surrogates::Utf16));
+        assert_eq!("[\"\u{FFFD}a\u{FFFD}\"]", rewrite(Surrogates::Replace));
+    }
+
+    #[test]
+    fn round_trips() {
+        let documents = [
+            r#"{"key": [1.5e10, "x\u0000y", {"": null}], "other": -0}"#,
+            "[\"\\u007F\u{7F}\", 0.000, 12E-3]",
+        ];
+        for document in documents {
+            let written = rewrite(LexerBytes::from(document));
+            let tokens = LexerBytes::from(document).collect::<Vec<_>>();
+            assert_eq!(tokens, LexerBytes::from(written.as_str()).collect::<Vec<_>>(), "{}", written);
+        }
+    }
+
+    #[test]
+    fn extensions() {
+        let input = "[1, // one\n/* two */ 2]";
+        let written = rewrite(LexerBytes::from(input).with_dialect(Dialect::Jsonc).keep_comments());
+        assert_eq!("[1, // one\n /* two */ 2]", written);
+        let input = "{a:+Infinity,b:-Infinity,c:-NaN,d:0xFF,e:.5,f:'x'}";
+        let written = rewrite(LexerBytes::from(input).with_dialect(Dialect::Json5));
+        assert_eq!("{a:Infinity,b:-Infinity,c:NaN,d:255,e:0.5,f:\"x\"}", written);
+    }
+
+    #[test]
+    fn iterators() {
+        let mut w = TokenWriter::new(Vec::new());
+        let tokens = LexerBytes::from("[1, 2]").filter_map(Result::ok).filter(|t| t != &Token::WhiteSpace);
+        w.write_tokens(tokens).unwrap();
+        assert_eq!(b"[1,2]".to_vec(), w.into_inner());
+    }
+}